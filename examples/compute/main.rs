@@ -0,0 +1,45 @@
+use std::error::Error;
+
+use tridify_rs::*;
+use wgpu::{Limits, ShaderStages};
+
+const VALUE_COUNT: u32 = 1000;
+const WORKGROUP_SIZE: u32 = 64;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //A window is needed to create the GPU context, even if nothing is drawn into it.
+    //Storage buffers and compute shaders need higher limits than the WebGL2 defaults.
+    let mut app = Tridify::with_options(TridifyOptions {
+        limits: Limits::downlevel_defaults(),
+        ..Default::default()
+    });
+    let window = app.create_window()?;
+    let gpu = window.ctx();
+
+    //Writable storage buffer that the shader fills with each element index.
+    let values =
+        StorageBuffer::<u32>::zeroed(gpu, VALUE_COUNT as usize, false, ShaderStages::COMPUTE);
+    let mut brush = ComputeBrush::from_source(gpu, include_str!("shader.wgsl").to_string())?;
+    brush.bind(0, 0, values.clone());
+
+    //Run enough workgroups to cover all values.
+    let mut pass = gpu.start_compute_pass();
+    pass.dispatch(
+        gpu,
+        &mut brush,
+        (VALUE_COUNT + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+        1,
+        1,
+    )?;
+    pass.finish(gpu);
+
+    //Read results back and check them.
+    let result = values.read_back(gpu);
+    assert!(result.iter().enumerate().all(|(i, v)| *v == i as u32));
+    println!(
+        "Compute shader filled {} values: {:?}...",
+        result.len(),
+        &result[..8]
+    );
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use glam::{Mat4, Vec2, Vec3};
+use tridify_rs::*;
+use winit::event::VirtualKeyCode;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    let mut camera = PerspectiveCamera::new(Vec3::new(0.0, 3.0, 6.0), Vec3::ZERO, 1.0);
+    camera.set_aspect_from_wnd(gpu_ctx);
+    let view_proj = GpuBuffer::init(
+        gpu_ctx,
+        bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+    );
+
+    //Floor and decal brushes share their settings, except the biased decal brush moves the decal
+    //towards the camera so it always wins the depth test against the coplanar floor.
+    let desc = || BrushDesc {
+        depth_test: true,
+        depth_write: true,
+        cull_mode: None,
+        ..Default::default()
+    };
+    let mut floor_brush =
+        Brush::from_source(desc(), gpu_ctx, include_str!("shader.wgsl").to_string())?;
+    floor_brush.bind(0, 0, view_proj.clone());
+    let mut biased_brush = Brush::from_source(
+        BrushDesc {
+            depth_bias: -2,
+            depth_bias_slope_scale: -1.0,
+            ..desc()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    biased_brush.bind(0, 0, view_proj.clone());
+    let mut unbiased_brush =
+        Brush::from_source(desc(), gpu_ctx, include_str!("shader.wgsl").to_string())?;
+    unbiased_brush.bind(0, 0, view_proj.clone());
+
+    //Decal lies exactly on the floor plane.
+    let floor = ShapeBatch::new()
+        .add_plane(Vec3::ZERO, Vec2::splat(6.0), 1, Color::SILVER)
+        .bake_buffers(gpu_ctx)?;
+    let decal = ShapeBatch::new()
+        .add_plane(Vec3::ZERO, Vec2::splat(2.0), 1, Color::RED)
+        .bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Orbit around so z-fighting is visible while moving.
+        let angle = frame_ctx.elapsed_time as f32 * 0.5;
+        camera.set_aspect_from_wnd(gpu);
+        camera.position = Vec3::new(angle.sin() * 6.0, 3.0, angle.cos() * 6.0);
+        view_proj.write(gpu, 0, &camera.view_proj().to_cols_array());
+
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions {
+            depth: true,
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut floor_brush, &floor)?;
+        //Hold space to draw the decal without bias and see it flicker.
+        let decal_brush = if gpu.input().is_key_down(VirtualKeyCode::Space) {
+            &mut unbiased_brush
+        } else {
+            &mut biased_brush
+        };
+        render_pass.render_shapes(gpu, decal_brush, &decal)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -23,8 +23,14 @@ fn main() -> Result<(), Box<dyn Error>> {
         //Here start to draw egui however you'd like
         egui_demo.ui(&gpu.egui_ctx());
 
-        //Process egui outputs and draw them into the screen
-        egui_pass.render(gpu);
+        //Clear the frame and draw egui on top of it
+        let mut pass_builder = gpu.create_render_builder()?;
+        pass_builder
+            .build_render_pass(RenderOptions::default())
+            .finish();
+        egui_pass.render_in(gpu, &mut pass_builder);
+        pass_builder.finish_render(gpu)?;
+        Ok(())
     });
 
     app.start(());
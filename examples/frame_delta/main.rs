@@ -0,0 +1,70 @@
+use std::{error::Error, thread, time::Duration};
+
+use tridify_rs::*;
+
+const MAX_FPS: u32 = 60;
+/// Simulated work per frame, cycled every few seconds. Loads above the frame budget slow down the
+/// frame rate and `delta_time` grows with them.
+const LOADS_MS: [u64; 4] = [0, 10, 25, 50];
+const SECONDS_PER_LOAD: f64 = 3.0;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Vsync is disabled so the frame rate is only limited by the cap.
+    let mut app = Tridify::new();
+    let window = app.create_window_with(
+        WindowSettings::builder()
+            .title("Frame delta")
+            .max_fps(MAX_FPS)
+            .vsync(false)
+            .build(),
+    )?;
+    let gpu_ctx = window.ctx();
+
+    let mut brush = Brush::from_source(
+        BrushDesc::default(),
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    let buffer = ShapeBatch::new()
+        .add_triangle([
+            vertex!(-0.5, -0.5, 0.0, Color::SILVER),
+            vertex!(0.5, -0.5, 0.0, Color::SILVER),
+            vertex!(0.0, 0.5, 0.0, Color::SILVER),
+        ])
+        .bake_buffers(gpu_ctx)?;
+
+    let budget_ms = 1000.0 / MAX_FPS as f64;
+    let mut frames = 0;
+    let mut timer = 0.0;
+    window.set_render_loop(move |gpu, frame_ctx| {
+        let load_index = (frame_ctx.elapsed_time / SECONDS_PER_LOAD) as usize % LOADS_MS.len();
+        let load_ms = LOADS_MS[load_index];
+
+        //Print the average frame delta every second.
+        frames += 1;
+        timer += frame_ctx.delta_time;
+        if timer >= 1.0 {
+            println!(
+                "Load {}ms: {:.2}ms per frame ({:.2}ms cap at {} FPS)",
+                load_ms,
+                timer * 1000.0 / frames as f64,
+                budget_ms,
+                MAX_FPS
+            );
+            frames = 0;
+            timer = 0.0;
+        }
+
+        //Block the render loop as if the frame had expensive logic.
+        thread::sleep(Duration::from_millis(load_ms));
+
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -1,6 +1,7 @@
-use std::error::Error;
+use std::{error::Error, path::Path};
 
 use tridify_rs::*;
+use winit::event::VirtualKeyCode;
 
 pub fn main() -> Result<(), Box<dyn Error>> {
     //Create app and main window.
@@ -24,19 +25,25 @@ pub fn main() -> Result<(), Box<dyn Error>> {
             vertex!(0.5, -0.5, 0.0, Color::SILVER),
             vertex!(0.0, 0.5, 0.0, Color::SILVER),
         ])
-        .bake_buffers(gpu_ctx);
+        .bake_buffers(gpu_ctx)?;
 
     window.set_render_loop(move |gpu, _| {
         //Create a render pass builder which we will use to define multiple render passes (In this case, only one).
-        let mut pass_builder = gpu.create_render_builder();
+        let mut pass_builder = gpu.create_render_builder()?;
 
         //Build a render pass which will take care of the brush and shapes to draw them and binding it with the GPU.
         let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
-        render_pass.render_shapes(gpu, &mut brush, &buffer);
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
         render_pass.finish();
 
         //Execute all drawing commands from all render passes and render into screen.
-        pass_builder.finish_render(gpu);
+        pass_builder.finish_render(gpu)?;
+
+        //Press F12 to save the next frame as a screenshot.
+        if gpu.input().was_key_pressed(VirtualKeyCode::F12) {
+            gpu.capture_frame(Path::new("screenshot.png"));
+        }
+        Ok(())
     });
 
     //Start program logic cycle.
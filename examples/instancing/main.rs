@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use glam::{Mat4, Quat, Vec3};
+use tridify_rs::*;
+
+const GRID_SIZE: u32 = 100;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window without frame cap to measure performance.
+    let mut app = Tridify::new();
+    let window = app.create_window_with(
+        WindowSettings::builder()
+            .title("Instancing")
+            .unlimited_fps()
+            .vsync(false)
+            .build(),
+    )?;
+    let gpu_ctx = window.ctx();
+
+    //Brush needs to be instanced to read per instance data.
+    let mut brush = Brush::from_source(
+        BrushDesc {
+            instanced: true,
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+
+    //One instance for each cell in the grid, each with its own transform and color.
+    let cell_size = 2.0 / GRID_SIZE as f32;
+    let instances: Vec<Instance> = (0..GRID_SIZE * GRID_SIZE)
+        .map(|i| {
+            let (x, y) = (i % GRID_SIZE, i / GRID_SIZE);
+            let pos = Vec3::new(
+                -1.0 + (x as f32 + 0.5) * cell_size,
+                -1.0 + (y as f32 + 0.5) * cell_size,
+                0.0,
+            );
+            let transform = Mat4::from_scale_rotation_translation(
+                Vec3::ONE * cell_size * 0.8,
+                Quat::IDENTITY,
+                pos,
+            );
+            let color = Color::from_hsv(
+                i as f32 * 360.0 / (GRID_SIZE * GRID_SIZE) as f32,
+                0.8,
+                1.0,
+                1.0,
+            );
+            Instance::new(transform, color)
+        })
+        .collect();
+
+    //Bake a single quad that will be drawn once per instance.
+    let buffer = ShapeBatch::new()
+        .add_2d_square(Vec3::ZERO, 1.0, 1.0, Color::WHITE)
+        .bake_instanced(gpu_ctx, &instances)?;
+
+    let mut frames = 0;
+    let mut timer = 0.0;
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Print the average frame time every second.
+        frames += 1;
+        timer += frame_ctx.delta_time;
+        if timer >= 1.0 {
+            println!(
+                "{} instances: {:.3}ms per frame",
+                instances.len(),
+                timer * 1000.0 / frames as f64
+            );
+            frames = 0;
+            timer = 0.0;
+        }
+
+        //All instances are drawn with a single draw call.
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -0,0 +1,50 @@
+use std::{error::Error, path::Path};
+
+use glam::{Mat4, Vec3};
+use tridify_rs::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Load model from an OBJ file. All meshes in the file are merged into one buffer.
+    let model = load_obj(gpu_ctx, Path::new(r#"examples/obj_model/model.obj"#))?;
+
+    let mut camera = PerspectiveCamera::new(Vec3::new(0.0, 3.0, 5.0), Vec3::Y * 0.5, 1.0);
+    camera.set_aspect_from_wnd(gpu_ctx);
+    let camera_buf = camera.build_buffer(gpu_ctx);
+
+    //Brush shades the model based on its texture coordinates.
+    let mut brush = Brush::from_source(
+        BrushDesc {
+            depth_test: true,
+            depth_write: true,
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    brush.bind(0, 0, camera_buf.clone());
+
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Spin the model around.
+        let model_matrix = Mat4::from_rotation_y(frame_ctx.elapsed_time as f32);
+        camera.set_aspect_from_wnd(gpu);
+        let mvp = camera.view_proj() * model_matrix;
+        camera_buf.write(gpu, 0, &mvp.to_cols_array());
+
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions {
+            depth: true,
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut brush, &model)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
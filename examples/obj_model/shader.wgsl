@@ -0,0 +1,35 @@
+struct Camera {
+    mvp: mat4x4<f32>, 
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u_camera: Camera;
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera.mvp * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    out.uv = model.uv;
+    return out;
+}
+
+
+// Fragment shader
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.uv, 1.0, 1.0) * in.color;
+}
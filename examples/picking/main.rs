@@ -0,0 +1,85 @@
+use std::error::Error;
+
+use glam::Vec3;
+use tridify_rs::*;
+use winit::event::MouseButton;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Objects that can be selected, their index + 1 is used as ID since 0 means nothing was hit.
+    let objects = [
+        ("Red square", Vec3::new(-0.5, 0.0, 0.0), Color::RED),
+        ("Green square", Vec3::new(0.0, 0.0, 0.0), Color::GREEN),
+        ("Blue square", Vec3::new(0.5, 0.0, 0.0), Color::BLUE),
+    ];
+
+    //Shapes drawn into the window with their color, and into the picking target with their ID.
+    let mut brush = Brush::from_source(
+        BrushDesc::default(),
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    let mut picking_brush = Brush::from_source(
+        BrushDesc {
+            targets: vec![PickingTarget::FORMAT],
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("picking.wgsl").to_string(),
+    )?;
+    let mut shapes = ShapeBatch::new();
+    let mut id_shapes = ShapeBatch::new();
+    for (i, (_, center, color)) in objects.iter().enumerate() {
+        shapes.add_2d_square(*center, 0.4, 0.4, *color);
+        //The picking shader reads the ID from the red channel.
+        id_shapes.add_2d_square(*center, 0.4, 0.4, Color::new(i as f32 + 1.0, 0.0, 0.0, 1.0));
+    }
+    let shapes = shapes.bake_buffers(gpu_ctx)?;
+    let id_shapes = id_shapes.bake_buffers(gpu_ctx)?;
+
+    let mut picking = PickingTarget::new(gpu_ctx, gpu_ctx.get_wnd_size());
+    let mut was_mouse_down = false;
+    window.set_render_loop(move |gpu, _| {
+        //Keep the picking target the same size as the window so pixels match the cursor.
+        if picking.size() != gpu.get_wnd_size() {
+            picking = PickingTarget::new(gpu, gpu.get_wnd_size());
+        }
+
+        let mut builder = gpu.create_render_builder()?;
+        let mut render_pass = builder.build_render_pass(RenderOptions {
+            clear_color: Some(Color::SILVER),
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut brush, &shapes)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+
+        //Only pick when the mouse is clicked, reading back pixels waits for the GPU.
+        let mouse_down = gpu.input().is_mouse_down(MouseButton::Left);
+        if mouse_down && !was_mouse_down {
+            let mut builder = picking.create_render_builder(gpu)?;
+            let mut render_pass = builder.build_render_pass(RenderOptions {
+                clear_color: Some(Color::new(0.0, 0.0, 0.0, 0.0)),
+                ..Default::default()
+            });
+            render_pass.render_shapes(gpu, &mut picking_brush, &id_shapes)?;
+            render_pass.finish();
+            builder.finish_render(gpu)?;
+
+            let cursor = gpu.input().mouse_position();
+            match picking.read_pixel(gpu, cursor.x as u32, cursor.y as u32)? {
+                0 => println!("Nothing selected"),
+                id => println!("Selected {} (ID {})", objects[id as usize - 1].0, id),
+            }
+        }
+        was_mouse_down = mouse_down;
+        Ok(())
+    });
+
+    //Start program logic cycle.
+    app.start(());
+}
@@ -0,0 +1,9 @@
+@group(0) @binding(0) var t_input: texture_2d<f32>;
+@group(0) @binding(1) var s_input: sampler;
+
+@fragment
+fn fs_main(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    let color = textureSample(t_input, s_input, in.uv);
+    let gray = dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
+    return vec4<f32>(gray, gray, gray, color.a);
+}
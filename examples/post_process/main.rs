@@ -0,0 +1,109 @@
+use std::error::Error;
+
+use glam::UVec2;
+use tridify_rs::*;
+use wgpu::TextureFormat;
+
+/// Size of the window, never zero so it can be used for textures.
+fn target_size(gpu: &GpuCtx) -> UVec2 { gpu.get_wnd_size().max(UVec2::ONE) }
+
+/// Create a texture the size of the window that can be drawn into and sampled afterwards.
+fn create_target(gpu: &GpuCtx, label: &str) -> Texture {
+    Texture::new(
+        gpu,
+        TextureDesc {
+            size: TextureSize::D2(target_size(gpu)),
+            usage: TextureUsage::RENDER | TextureUsage::TEXTURE_BIND,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+        },
+        Some(label),
+    )
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Textures each step is drawn into. They need RENDER usage to be drawn into and TEXTURE_BIND to
+    //be sampled by the next step.
+    let mut scene = create_target(gpu_ctx, "Scene texture");
+    let mut grayscale = create_target(gpu_ctx, "Grayscale texture");
+
+    //Brush drawing the scene, its target format must match the texture format.
+    let mut scene_brush = Brush::from_source(
+        BrushDesc {
+            targets: vec![scene.desc.format],
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("scene.wgsl").to_string(),
+    )?;
+    let triangle = ShapeBatch::new()
+        .add_triangle([
+            vertex!(-0.5, -0.5, 0.0, Color::RED),
+            vertex!(0.5, -0.5, 0.0, Color::GREEN),
+            vertex!(0.0, 0.5, 0.0, Color::BLUE),
+        ])
+        .bake_buffers(gpu_ctx)?;
+
+    //Fullscreen passes only need the fragment shader. Cloning a texture shares the same GPU
+    //texture, so the one drawn into before can be bound.
+    let mut grayscale_pass = FullscreenPass::from_source(
+        BrushDesc {
+            targets: vec![grayscale.desc.format],
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("grayscale.wgsl"),
+    )?;
+    grayscale_pass.bind(0, 0, scene.clone());
+    grayscale_pass.bind(0, 1, Sampler::linear_clamp(gpu_ctx));
+
+    //Last effect draws into the window.
+    let mut invert_pass =
+        FullscreenPass::from_source(BrushDesc::default(), gpu_ctx, include_str!("invert.wgsl"))?;
+    invert_pass.bind(0, 0, grayscale.clone());
+    invert_pass.bind(0, 1, Sampler::linear_clamp(gpu_ctx));
+
+    window.set_render_loop(move |gpu, _| {
+        //Keep the textures the same size as the window. Brushes keep the old textures until they
+        //are bound again.
+        let size = target_size(gpu);
+        if scene.desc.size.get_size().truncate() != size {
+            scene.resize(gpu, TextureSize::D2(size));
+            grayscale.resize(gpu, TextureSize::D2(size));
+            grayscale_pass.bind(0, 0, scene.clone());
+            invert_pass.bind(0, 0, grayscale.clone());
+        }
+
+        //First draw the scene into a texture.
+        let mut builder = gpu.create_texture_render_builder(&scene)?;
+        let mut render_pass = builder.build_render_pass(RenderOptions {
+            clear_color: Some(Color::SILVER),
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut scene_brush, &triangle)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+
+        //Then apply each effect using the result of the previous one.
+        let mut builder = gpu.create_texture_render_builder(&grayscale)?;
+        let mut render_pass = builder.build_render_pass(RenderOptions::default());
+        render_pass.render_fullscreen(gpu, &mut grayscale_pass)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+
+        let mut builder = gpu.create_render_builder()?;
+        let mut render_pass = builder.build_render_pass(RenderOptions::default());
+        render_pass.render_fullscreen(gpu, &mut invert_pass)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    //Start program logic cycle.
+    app.start(());
+}
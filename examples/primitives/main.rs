@@ -0,0 +1,64 @@
+use std::error::Error;
+
+use glam::{Mat4, Quat, Vec2, Vec3};
+use tridify_rs::*;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    let mut camera = PerspectiveCamera::new(Vec3::new(0.0, 4.0, 8.0), Vec3::ZERO, 1.0);
+    camera.set_aspect_from_wnd(gpu_ctx);
+
+    //Uniform with the camera view projection followed by the model matrix, so normals can be
+    //rotated with the shapes.
+    let matrices = GpuBuffer::init(
+        gpu_ctx,
+        bytemuck::cast_slice(&[Mat4::IDENTITY.to_cols_array(); 2]),
+    );
+
+    //Brush lights the shapes using their normals. Back faces are culled and depth is used so
+    //near faces hide far ones.
+    let mut brush = Brush::from_source(
+        BrushDesc {
+            depth_test: true,
+            depth_write: true,
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    brush.bind(0, 0, matrices.clone());
+
+    //Generate the shapes, each one with positions, normals and texture coordinates.
+    let shapes = ShapeBatch::new()
+        .add_cube(Vec3::X * -2.0, Quat::IDENTITY, Vec3::ONE * 1.5, Color::RED)
+        .add_uv_sphere(Vec3::X * 2.0, 1.0, 16, 32, Color::BLUE_AQUA)
+        .add_plane(Vec3::Y * -1.5, Vec2::splat(8.0), 8, Color::SILVER)
+        .bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Spin everything around.
+        let model = Mat4::from_rotation_y(frame_ctx.elapsed_time as f32 * 0.5);
+        camera.set_aspect_from_wnd(gpu);
+        matrices.write(
+            gpu,
+            0,
+            &[camera.view_proj().to_cols_array(), model.to_cols_array()],
+        );
+
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions {
+            depth: true,
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut brush, &shapes)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -0,0 +1,40 @@
+struct Matrices {
+    view_proj: mat4x4<f32>,
+    model: mat4x4<f32>,
+};
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) normal: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u_matrices: Matrices;
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_matrices.view_proj * u_matrices.model * vec4<f32>(model.position, 1.0);
+    //Model matrix only rotates, so it can be used for normals too.
+    out.normal = (u_matrices.model * vec4<f32>(model.normal, 0.0)).xyz;
+    out.color = model.color;
+    return out;
+}
+
+// Fragment shader
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //Lambert lighting with a directional light and some ambient light.
+    let light_dir = normalize(vec3<f32>(0.5, 1.0, 0.3));
+    let diffuse = max(dot(normalize(in.normal), light_dir), 0.0);
+    return vec4<f32>(in.color.rgb * (0.2 + 0.8 * diffuse), in.color.a);
+}
@@ -0,0 +1,54 @@
+use std::error::Error;
+
+use glam::Vec3;
+use tridify_rs::*;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Brush drawing tessellated circles with their vertex color.
+    let mut fan_brush = Brush::from_source(
+        BrushDesc {
+            sample_count: gpu_ctx.sample_count(),
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    //Built in brush computing the circle edge per pixel.
+    let mut sdf_brush = Brush::sdf_circle(gpu_ctx)?;
+
+    //Top row uses triangle fans, bottom row the same circles as single quads. Small circles show
+    //the facets of fans the most.
+    let radii = [0.02, 0.05, 0.1, 0.2];
+    let mut fans = ShapeBatch::new();
+    let mut sdf_circles = ShapeBatch::new();
+    let mut x = -0.8;
+    for radius in radii {
+        x += radius;
+        fans.add_circle(vertex!(x, 0.4, 0.0, Color::RED), radius, 16);
+        sdf_circles.add_sdf_circle(Vec3::new(x, -0.4, 0.0), radius, Color::RED);
+        x += radius + 0.1;
+    }
+    let fans = fans.bake_buffers(gpu_ctx)?;
+    let sdf_circles = sdf_circles.bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, _| {
+        let mut builder = gpu.create_render_builder()?;
+        let mut render_pass = builder.build_render_pass(RenderOptions {
+            clear_color: Some(Color::WHITE),
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut fan_brush, &fans)?;
+        render_pass.render_shapes(gpu, &mut sdf_brush, &sdf_circles)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    //Start program logic cycle.
+    app.start(());
+}
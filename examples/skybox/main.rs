@@ -0,0 +1,82 @@
+use std::error::Error;
+
+use glam::Vec3;
+use tridify_rs::*;
+
+const FACE_SIZE: u32 = 64;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Create cube map and fill each face with its own color, darker towards the bottom. Use
+    //`Texture::cube_from_paths` to load faces from images instead.
+    let sky = Texture::new(
+        gpu_ctx,
+        TextureDesc {
+            size: TextureSize::Cube(FACE_SIZE),
+            usage: TextureUsage::TEXTURE_BIND | TextureUsage::DESTINATION,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+        },
+        None,
+    );
+    let face_colors = [
+        Color::RED,
+        Color::BLUE_TEAL,
+        Color::WHITE,
+        Color::BLACK,
+        Color::BLUE,
+        Color::YELLOW,
+    ];
+    for (layer, color) in face_colors.into_iter().enumerate() {
+        let pixels: Vec<u8> = (0..FACE_SIZE * FACE_SIZE)
+            .flat_map(|i| {
+                let shade = 1.0 - (i / FACE_SIZE) as f32 / FACE_SIZE as f32 * 0.5;
+                let color = color * shade;
+                [color.r, color.g, color.b, 1.0].map(|c| (c * 255.0) as u8)
+            })
+            .collect();
+        sky.write_layer_pixels(gpu_ctx, layer as u32, &pixels)?;
+    }
+
+    let mut camera = PerspectiveCamera::new(Vec3::ZERO, Vec3::NEG_Z, 1.0);
+    let camera_buf = UniformBuffer::new(gpu_ctx, camera.view_proj().inverse().to_cols_array());
+
+    let mut brush = Brush::from_source(
+        BrushDesc::default(),
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    brush.bind(0, 0, camera_buf.clone());
+    brush.bind(1, 0, sky);
+    brush.bind(1, 1, Sampler::linear_clamp(gpu_ctx));
+
+    //Single triangle covering the whole screen.
+    let buffer = ShapeBatch::new()
+        .add_triangle([
+            vertex!(-1.0, -1.0, 0.0),
+            vertex!(3.0, -1.0, 0.0),
+            vertex!(-1.0, 3.0, 0.0),
+        ])
+        .bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Look around the horizon.
+        let angle = frame_ctx.elapsed_time as f32 * 0.5;
+        camera.look_at(Vec3::new(angle.sin(), 0.3, -angle.cos()));
+        camera.set_aspect_from_wnd(gpu);
+        camera_buf.update(gpu, camera.view_proj().inverse().to_cols_array());
+
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -0,0 +1,33 @@
+struct Camera {
+    inv_view_proj: mat4x4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u_camera: Camera;
+@group(1) @binding(0) var t_sky: texture_cube<f32>;
+@group(1) @binding(1) var s_sky: sampler;
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position.xy, 1.0, 1.0);
+    out.ndc = model.position.xy;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    //Camera sits at the origin, so the unprojected point is the view direction.
+    let world = u_camera.inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    return textureSample(t_sky, s_sky, world.xyz / world.w);
+}
@@ -0,0 +1,102 @@
+use std::error::Error;
+
+use glam::Vec3;
+use tridify_rs::*;
+use wgpu::{
+    BlendComponent, BlendFactor, BlendOperation, BlendState, CompareFunction, StencilFaceState,
+    StencilOperation, StencilState,
+};
+
+/// Stencil state applying the same test and operation to both faces.
+fn stencil(compare: CompareFunction, pass_op: StencilOperation) -> StencilState {
+    let face = StencilFaceState {
+        compare,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op,
+    };
+    StencilState {
+        front: face,
+        back: face,
+        read_mask: 0xff,
+        write_mask: 0xff,
+    }
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window with a stencil buffer.
+    let mut app = Tridify::new();
+    let window = app.create_window_with(WindowSettings::builder().stencil(true).build())?;
+    let gpu_ctx = window.ctx();
+
+    //Mask brush only writes the reference value into the stencil buffer. Its blend state keeps the
+    //target color, so the mask itself is invisible.
+    let keep_target = BlendComponent {
+        src_factor: BlendFactor::Zero,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    };
+    let mut mask_brush = Brush::from_source(
+        BrushDesc {
+            blend: BlendMode::Custom(BlendState {
+                color: keep_target,
+                alpha: keep_target,
+            }),
+            stencil: stencil(CompareFunction::Always, StencilOperation::Replace),
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+    //Content brush only draws where the stencil value matches the reference.
+    let mut content_brush = Brush::from_source(
+        BrushDesc {
+            stencil: stencil(CompareFunction::Equal, StencilOperation::Keep),
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+
+    let mask = ShapeBatch::new()
+        .add_circle(vertex!(0.0, 0.0, 0.0), 0.6, 64)
+        .bake_buffers(gpu_ctx)?;
+    //Checkerboard covering the whole window, clipped to the circle.
+    let mut content = ShapeBatch::new();
+    let cells = 8;
+    let cell_size = 2.0 / cells as f32;
+    for y in 0..cells {
+        for x in 0..cells {
+            let center = Vec3::new(
+                -1.0 + (x as f32 + 0.5) * cell_size,
+                -1.0 + (y as f32 + 0.5) * cell_size,
+                0.0,
+            );
+            let color = if (x + y) % 2 == 0 {
+                Color::RED
+            } else {
+                Color::BLUE
+            };
+            content.add_2d_square(center, cell_size, cell_size, color);
+        }
+    }
+    let content = content.bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, _| {
+        let mut builder = gpu.create_render_builder()?;
+        //Depth attaches the depth and stencil buffer, with stencil values cleared to 0.
+        let mut render_pass = builder.build_render_pass(RenderOptions {
+            clear_color: Some(Color::SILVER),
+            depth: true,
+        });
+        render_pass.set_stencil_reference(1);
+        render_pass.render_shapes(gpu, &mut mask_brush, &mask)?;
+        render_pass.render_shapes(gpu, &mut content_brush, &content)?;
+        render_pass.finish();
+        builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    //Start program logic cycle.
+    app.start(());
+}
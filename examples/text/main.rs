@@ -0,0 +1,38 @@
+use std::error::Error;
+
+use tridify_rs::*;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window.
+    let mut app = Tridify::new();
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Text brush comes with the embedded font atlas already bound.
+    let mut brush = Brush::text(gpu_ctx)?;
+
+    //Each glyph is added as a quad, using the vertex as the top left corner and color.
+    let buffer = ShapeBatch::new()
+        .add_text(
+            "Hello, Tridify!\nSecond line.",
+            vertex!(-0.9, 0.9, 0.0, Color::WHITE),
+            0.1,
+        )
+        .add_text(
+            "Missing glyph: \u{00F1}",
+            vertex!(-0.9, 0.5, 0.0, Color::RED),
+            0.08,
+        )
+        .bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, _| {
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -15,15 +15,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     //Sampler defines how the texture will be rendered in shapes.
     let sampler = Sampler::new_default(gpu_ctx);
 
-    let camera = Camera::new(
-        Transform::from_look_at(Vec3::NEG_Z * 10.0 + Vec3::Y * 10.0, Vec3::ZERO, Vec3::Y),
-        Projection::default(),
-    );
-    let mut camera_buf = camera.build_buffer(gpu_ctx);
+    let mut camera = PerspectiveCamera::new(Vec3::NEG_Z * 10.0 + Vec3::Y * 10.0, Vec3::ZERO, 1.0);
+    camera.set_aspect_from_wnd(gpu_ctx);
+    let camera_buf = camera.build_buffer(gpu_ctx);
 
-    //Create brush to draw the shapes.
+    //Create brush to draw the shapes, using depth so near faces hide far ones.
     let mut brush = Brush::from_source(
-        BrushDesc::default(),
+        BrushDesc {
+            depth_test: true,
+            depth_write: true,
+            ..Default::default()
+        },
         gpu_ctx,
         include_str!("shader.wgsl").to_string(),
     )?;
@@ -41,22 +43,27 @@ fn main() -> Result<(), Box<dyn Error>> {
             Vec3::ONE * 5.,
             Color::WHITE,
         )
-        .bake_buffers(gpu_ctx);
+        .bake_buffers(gpu_ctx)?;
 
     //Setup the window render loop.
     window.set_render_loop(move |gpu, frame_ctx| {
         let model = Mat4::from_rotation_y(frame_ctx.elapsed_time as f32);
-        let mvp = camera.build_camera_matrix() * model;
+        camera.set_aspect_from_wnd(gpu);
+        let mvp = camera.view_proj() * model;
 
         //Updating the gpu buffer will update all brushes binded as well.
-        camera_buf.write(gpu, bytemuck::cast_slice(&mvp.to_cols_array()));
+        camera_buf.write(gpu, 0, &mvp.to_cols_array());
 
         //Render frame as usual.
-        let mut pass_builder = gpu.create_render_builder();
-        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
-        render_pass.render_shapes(gpu, &mut brush, &shape_buffer);
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions {
+            depth: true,
+            ..Default::default()
+        });
+        render_pass.render_shapes(gpu, &mut brush, &shape_buffer)?;
         render_pass.finish();
-        pass_builder.finish_render(gpu);
+        pass_builder.finish_render(gpu)?;
+        Ok(())
     });
 
     // Start program.
@@ -0,0 +1,84 @@
+use std::error::Error;
+
+use glam::{UVec2, Vec2};
+use tridify_rs::*;
+
+const GRID_SIZE: u32 = 100;
+const TILE_SIZE: u32 = 16;
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Create app and main window without frame cap to measure performance.
+    let mut app = Tridify::new();
+    let window = app.create_window_with(
+        WindowSettings::builder()
+            .title("Tilemap")
+            .unlimited_fps()
+            .vsync(false)
+            .build(),
+    )?;
+    let gpu_ctx = window.ctx();
+
+    //Atlas with 2x2 tiles of different colors, each one with a darker border.
+    let tile_colors = [Color::GREEN, Color::BLUE, Color::SILVER, Color::RED];
+    let atlas = Texture::from_fn(
+        gpu_ctx,
+        UVec2::splat(TILE_SIZE * 2),
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        |x, y| {
+            let color = tile_colors[((y / TILE_SIZE) * 2 + x / TILE_SIZE) as usize];
+            let (x, y) = (x % TILE_SIZE, y % TILE_SIZE);
+            if x == 0 || y == 0 || x == TILE_SIZE - 1 || y == TILE_SIZE - 1 {
+                Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, 1.0)
+            } else {
+                color
+            }
+        },
+    )?;
+    let mut brush = Brush::sprite(gpu_ctx, &atlas, &Sampler::nearest_repeat(gpu_ctx))?;
+
+    //Sprites are added once and only uploaded again if they change.
+    let cell_size = 2.0 / GRID_SIZE as f32;
+    let mut tiles = SpriteBatch::new(gpu_ctx)?;
+    for i in 0..GRID_SIZE * GRID_SIZE {
+        let (x, y) = (i % GRID_SIZE, i / GRID_SIZE);
+        let tile = (x * 7 + y * 13 + x * y) % 4;
+        tiles.add(
+            Rect::new(
+                Vec2::new(-1.0 + x as f32 * cell_size, -1.0 + y as f32 * cell_size),
+                Vec2::splat(cell_size),
+            ),
+            Rect::new(
+                Vec2::new((tile % 2) as f32 * 0.5, (tile / 2) as f32 * 0.5),
+                Vec2::splat(0.5),
+            ),
+            Color::WHITE,
+        );
+    }
+
+    let mut frames = 0;
+    let mut timer = 0.0;
+    window.set_render_loop(move |gpu, frame_ctx| {
+        //Print the average frame time every second.
+        frames += 1;
+        timer += frame_ctx.delta_time;
+        if timer >= 1.0 {
+            println!(
+                "{} tiles: {:.3}ms per frame",
+                tiles.len(),
+                timer * 1000.0 / frames as f64
+            );
+            frames = 0;
+            timer = 0.0;
+        }
+
+        //Whole grid is drawn with a single draw call.
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        tiles.flush(gpu, &mut render_pass, &mut brush)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+
+use tridify_rs::*;
+use wgpu::{Features, PolygonMode};
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    //Drawing lines instead of filled polygons is an optional device feature.
+    let mut app = Tridify::with_options(TridifyOptions {
+        features: Features::POLYGON_MODE_LINE,
+        ..Default::default()
+    });
+    let window = app.create_window()?;
+    let gpu_ctx = window.ctx();
+
+    //Only the edges of each triangle are rasterized.
+    let mut brush = Brush::from_source(
+        BrushDesc {
+            polygon_mode: PolygonMode::Line,
+            ..Default::default()
+        },
+        gpu_ctx,
+        include_str!("shader.wgsl").to_string(),
+    )?;
+
+    let buffer = ShapeBatch::new()
+        .add_triangle([
+            vertex!(-0.5, -0.5, 0.0, Color::SILVER),
+            vertex!(0.5, -0.5, 0.0, Color::SILVER),
+            vertex!(0.0, 0.5, 0.0, Color::SILVER),
+        ])
+        .bake_buffers(gpu_ctx)?;
+
+    window.set_render_loop(move |gpu, _| {
+        let mut pass_builder = gpu.create_render_builder()?;
+        let mut render_pass = pass_builder.build_render_pass(RenderOptions::default());
+        render_pass.render_shapes(gpu, &mut brush, &buffer)?;
+        render_pass.finish();
+        pass_builder.finish_render(gpu)?;
+        Ok(())
+    });
+
+    app.start(());
+}
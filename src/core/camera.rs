@@ -1,4 +1,4 @@
-use glam::Mat4;
+use glam::{Mat4, Vec2, Vec3};
 
 use crate::{GpuBuffer, GpuCtx, ToGpuBuf, Transform};
 
@@ -57,3 +57,130 @@ impl ToGpuBuf for Camera {
         )
     }
 }
+
+/// Orthographic camera for 2D rendering, working in pixels with the Y axis pointing down.
+pub struct Camera2D {
+    /// Position in pixels placed at the camera origin.
+    pub position: Vec2,
+    /// Visible area in pixels when zoom is 1.
+    pub size: Vec2,
+    /// Where the position is placed on screen, from (0, 0) at the top left to (1, 1) at the
+    /// bottom right.
+    pub origin: Vec2,
+    pub zoom: f32,
+}
+impl Camera2D {
+    pub fn new(position: Vec2, size: Vec2, origin: Vec2, zoom: f32) -> Self {
+        Self {
+            position,
+            size,
+            origin,
+            zoom,
+        }
+    }
+
+    /// Camera mapping each unit to one pixel with the origin at the top left of the screen.
+    pub fn screen(width: f32, height: f32) -> Self {
+        Self::new(Vec2::ZERO, Vec2::new(width, height), Vec2::ZERO, 1.0)
+    }
+
+    /// View projection matrix mapping the visible pixels to clip space.
+    pub fn matrix(&self) -> Mat4 {
+        let visible = self.size / self.zoom;
+        let top_left = self.position - self.origin * visible;
+        let bottom_right = top_left + visible;
+        Mat4::orthographic_rh(
+            top_left.x,
+            bottom_right.x,
+            bottom_right.y,
+            top_left.y,
+            -1.0,
+            1.0,
+        )
+    }
+}
+
+impl ToGpuBuf for Camera2D {
+    fn build_buffer(&self, wnd: &GpuCtx) -> crate::GpuBuffer {
+        GpuBuffer::init(wnd, bytemuck::cast_slice(&self.matrix().to_cols_array()))
+    }
+}
+
+/// Perspective camera looking at a target point, using right handed coordinates.
+pub struct PerspectiveCamera {
+    pub position: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    /// Vertical field of view in degrees.
+    pub fov_y: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+impl PerspectiveCamera {
+    pub fn new(position: Vec3, target: Vec3, aspect: f32) -> Self {
+        Self {
+            position,
+            target,
+            aspect,
+            ..Default::default()
+        }
+    }
+
+    /// Point the camera towards the target.
+    pub fn look_at(&mut self, target: Vec3) { self.target = target; }
+
+    pub fn set_aspect(&mut self, aspect: f32) { self.aspect = aspect; }
+
+    /// Set aspect ratio based on the current window size.
+    pub fn set_aspect_from_wnd(&mut self, gpu: &GpuCtx) {
+        let size = gpu.get_wnd_size().max(glam::UVec2::ONE);
+        self.aspect = size.x as f32 / size.y as f32;
+    }
+
+    pub fn view_matrix(&self) -> Mat4 { Mat4::look_at_rh(self.position, self.target, self.up) }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        Mat4::perspective_rh(self.fov_y.to_radians(), self.aspect, self.near, self.far)
+    }
+
+    /// Combined view and projection matrix, ready to be sent to shaders.
+    pub fn view_proj(&self) -> Mat4 { self.projection_matrix() * self.view_matrix() }
+}
+impl Default for PerspectiveCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::Z * 10.0,
+            target: Vec3::ZERO,
+            up: Vec3::Y,
+            fov_y: 65.0,
+            aspect: 16.0 / 9.0,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl ToGpuBuf for PerspectiveCamera {
+    fn build_buffer(&self, wnd: &GpuCtx) -> crate::GpuBuffer {
+        GpuBuffer::init(wnd, bytemuck::cast_slice(&self.view_proj().to_cols_array()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_projects_to_screen_center() {
+        let mut camera = PerspectiveCamera::new(Vec3::new(3.0, 4.0, 5.0), Vec3::ZERO, 4.0 / 3.0);
+        for target in [Vec3::ZERO, Vec3::new(-2.0, 1.0, 0.5)] {
+            camera.look_at(target);
+            let clip = camera.view_proj() * target.extend(1.0);
+            let ndc = clip.truncate() / clip.w;
+            assert!(ndc.x.abs() < 1e-5 && ndc.y.abs() < 1e-5, "{:?}", ndc);
+            //In front of the camera and inside the depth range.
+            assert!(clip.w > 0.0 && (0.0..=1.0).contains(&ndc.z), "{:?}", ndc);
+        }
+    }
+}
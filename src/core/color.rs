@@ -1,17 +1,27 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    ops::{Add, Mul},
+};
+
 use bytemuck::{Pod, Zeroable};
 
 /// RGBA representation of colors. Each value goes from 0 to 1.
 ///
-/// You can use the constants for some default values.
+/// You can use the constants for some default values. Constants are defined in sRGB space, use
+/// [`Color::to_linear`] when they need to be used in linear space, like when blending in shaders.
 ///
 /// #Examples
 /// ``` rust
-/// use ldrawy::Color;
+/// use tridify_rs::Color;
 /// let white_color = Color::WHITE;
 /// let white_color = Color::new(1.0, 1.0, 1.0, 1.0);
 /// ```
+///
+/// With the `serde` feature colors are serialized as an object with `r`, `g`, `b` and `a` fields.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -22,6 +32,95 @@ pub struct Color {
 impl Color {
     pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self { Self { r, g, b, a } }
 
+    /// Create color from 8 bit channels, where 255 maps to 1.0.
+    pub const fn from_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Parse color from an hexadecimal string. Accepts `RGB`, `RGBA`, `RRGGBB` and `RRGGBBAA`
+    /// formats, with or without a leading `#`.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::Color;
+    /// let blue = Color::from_hex("#3366CC").unwrap();
+    /// let transparent_blue = Color::from_hex("36C8").unwrap();
+    /// ```
+    pub fn from_hex(hex: &str) -> Result<Color, ColorParseError> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorParseError::InvalidCharacter);
+        }
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).unwrap();
+            //Short formats repeat each digit, so 'F' becomes 'FF'.
+            if len == 1 {
+                value * 17
+            } else {
+                value
+            }
+        };
+        match hex.len() {
+            3 => Ok(Self::from_u8(
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+                255,
+            )),
+            4 => Ok(Self::from_u8(
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+                channel(3, 1),
+            )),
+            6 => Ok(Self::from_u8(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                255,
+            )),
+            8 => Ok(Self::from_u8(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            )),
+            len => Err(ColorParseError::InvalidLength(len)),
+        }
+    }
+
+    /// Format color as an uppercase hexadecimal string, `#RRGGBB` for opaque colors and
+    /// `#RRGGBBAA` otherwise. Channels are clamped and rounded to the nearest 8 bit value, so
+    /// colors parsed with `from_hex` give back the same string.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::Color;
+    /// assert_eq!(Color::RED.to_hex(), "#FF0000");
+    /// assert_eq!(Color::from_hex("#3366CC80").unwrap().to_hex(), "#3366CC80");
+    /// assert_eq!(Color::from_hex("abc").unwrap().to_hex(), "#AABBCC");
+    /// ```
+    pub fn to_hex(&self) -> String {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let (r, g, b, a) = (
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            channel(self.a),
+        );
+        if a == 255 {
+            format!("#{:02X}{:02X}{:02X}", r, g, b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+        }
+    }
+
+    // Color constants, defined in sRGB space.
     pub const CLEAR: Color = Color::new(0.0, 0.0, 0.0, 0.0);
     pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
     pub const GRAY: Color = Color::new(0.5, 0.5, 0.5, 1.0);
@@ -38,6 +137,113 @@ impl Color {
     pub const BLUE_TEAL: Color = Color::new(0.0, 0.5, 0.5, 1.0);
     pub const BLUE_AQUA: Color = Color::new(0.0, 1.0, 1.0, 1.0);
 
+    /// Create color from hue in degrees, saturation and value between 0 and 1. Hue wraps around
+    /// so any value is valid.
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Self {
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+        Self::new(r + m, g + m, b + m, a)
+    }
+
+    /// Create color from hue in degrees, saturation and lightness between 0 and 1. Hue wraps
+    /// around so any value is valid.
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c / 2.0;
+        Self::new(r + m, g + m, b + m, a)
+    }
+
+    /// Returns hue in degrees, saturation and value. Alpha is ignored.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let s = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (self.hue(max, min), s, max)
+    }
+
+    /// Returns hue in degrees, saturation and lightness. Alpha is ignored.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = (max + min) / 2.0;
+        let s = if max - min > 0.0 {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        } else {
+            0.0
+        };
+        (self.hue(max, min), s, l)
+    }
+
+    fn hue(&self, max: f32, min: f32) -> f32 {
+        let delta = max - min;
+        if delta <= 0.0 {
+            return 0.0;
+        }
+        let h = if max == self.r {
+            (self.g - self.b) / delta
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.0
+        } else {
+            (self.r - self.g) / delta + 4.0
+        };
+        (h * 60.0).rem_euclid(360.0)
+    }
+
+    /// Linearly interpolate each channel towards `other`, with `t` clamped between 0 and 1.
+    /// Interpolation is done on the stored values, so colors defined in sRGB (like the provided
+    /// constants) are blended in sRGB space.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Convert from sRGB to linear space using the sRGB transfer function on each channel. Alpha
+    /// is already linear and is kept as is.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::Color;
+    /// let linear = Color::new(0.5, 0.5, 0.5, 0.5).to_linear();
+    /// assert!((linear.r - 0.2140).abs() < 1e-4);
+    /// assert_eq!(linear.a, 0.5);
+    /// ```
+    pub fn to_linear(self) -> Color {
+        let channel = |c: f32| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Color::new(channel(self.r), channel(self.g), channel(self.b), self.a)
+    }
+
+    /// Convert from linear to sRGB space, the inverse of [`Color::to_linear`]. Alpha is kept as is.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::Color;
+    /// let srgb = Color::new(0.214, 0.214, 0.214, 1.0).to_srgb();
+    /// assert!((srgb.g - 0.5).abs() < 1e-3);
+    /// ```
+    pub fn to_srgb(self) -> Color {
+        let channel = |c: f32| {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Color::new(channel(self.r), channel(self.g), channel(self.b), self.a)
+    }
+
     pub fn to_rgba8(&self) -> [u8; 4] {
         [
             (self.r * 255.0) as u8,
@@ -48,6 +254,64 @@ impl Color {
     }
 }
 
+/// Returns RGB channels for the given hue and chroma, without lightness offset.
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Color::new(self.r * rhs, self.g * rhs, self.b * rhs, self.a * rhs)
+    }
+}
+
+impl Add<Color> for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Self::Output {
+        Color::new(
+            self.r + rhs.r,
+            self.g + rhs.g,
+            self.b + rhs.b,
+            self.a + rhs.a,
+        )
+    }
+}
+
+/// Error returned when a color can't be parsed from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorParseError {
+    /// Amount of hexadecimal digits does not match any of the supported formats.
+    InvalidLength(usize),
+    /// String contains characters that are not hexadecimal digits.
+    InvalidCharacter,
+}
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorParseError::InvalidLength(len) => write!(
+                f,
+                "Invalid hex color length {}, expected 3, 4, 6 or 8 digits.",
+                len
+            ),
+            ColorParseError::InvalidCharacter => f.write_str("Invalid hex color character."),
+        }
+    }
+}
+impl Error for ColorParseError {
+}
+
 impl From<Color> for wgpu::Color {
     fn from(val: Color) -> Self {
         wgpu::Color {
@@ -69,3 +333,113 @@ impl From<egui::Color32> for Color {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Color, b: Color) {
+        let channels = |c: Color| [c.r, c.g, c.b, c.a];
+        let close = channels(a)
+            .iter()
+            .zip(channels(b))
+            .all(|(x, y)| (x - y).abs() < 1e-5);
+        assert!(close, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn from_hex_formats() {
+        let opaque = Color::from_u8(0x33, 0x66, 0xCC, 255);
+        let translucent = Color::from_u8(0x33, 0x66, 0xCC, 0x88);
+        for hex in ["36C", "#36C", "3366CC", "#3366CC", "3366cc"] {
+            assert_eq!(Color::from_hex(hex), Ok(opaque), "{}", hex);
+        }
+        for hex in ["36C8", "#36C8", "3366CC88", "#3366CC88"] {
+            assert_eq!(Color::from_hex(hex), Ok(translucent), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn from_hex_invalid_length() {
+        assert_eq!(Color::from_hex(""), Err(ColorParseError::InvalidLength(0)));
+        assert_eq!(
+            Color::from_hex("#12345"),
+            Err(ColorParseError::InvalidLength(5))
+        );
+        assert_eq!(
+            Color::from_hex("123456789"),
+            Err(ColorParseError::InvalidLength(9))
+        );
+    }
+
+    #[test]
+    fn from_hex_invalid_character() {
+        assert_eq!(
+            Color::from_hex("#12G"),
+            Err(ColorParseError::InvalidCharacter)
+        );
+        assert_eq!(
+            Color::from_hex("##123"),
+            Err(ColorParseError::InvalidCharacter)
+        );
+        assert_eq!(
+            Color::from_hex("12 456"),
+            Err(ColorParseError::InvalidCharacter)
+        );
+    }
+
+    #[test]
+    fn lerp_endpoints_and_clamping() {
+        let a = Color::new(0.0, 0.2, 0.4, 1.0);
+        let b = Color::new(1.0, 0.6, 0.0, 0.5);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, -1.0), a);
+        assert_eq!(a.lerp(b, 2.0), b);
+        assert_close(a.lerp(b, 0.5), Color::new(0.5, 0.4, 0.2, 0.75));
+    }
+
+    #[test]
+    fn hsv_hsl_primaries() {
+        let primaries = [
+            (0.0, Color::RED),
+            (120.0, Color::LIME),
+            (240.0, Color::BLUE),
+        ];
+        for (hue, color) in primaries {
+            assert_close(Color::from_hsv(hue, 1.0, 1.0, 1.0), color);
+            assert_close(Color::from_hsl(hue, 1.0, 0.5, 1.0), color);
+            let (h, s, v) = color.to_hsv();
+            assert!(
+                (h - hue).abs() < 1e-3 && s == 1.0 && v == 1.0,
+                "{:?}",
+                color
+            );
+            let (h, s, l) = color.to_hsl();
+            assert!(
+                (h - hue).abs() < 1e-3 && s == 1.0 && l == 0.5,
+                "{:?}",
+                color
+            );
+        }
+        //Hue wraps around.
+        assert_close(Color::from_hsv(360.0, 1.0, 1.0, 1.0), Color::RED);
+        assert_close(Color::from_hsv(-120.0, 1.0, 1.0, 1.0), Color::BLUE);
+    }
+
+    #[test]
+    fn hsv_hsl_round_trip() {
+        let colors = [
+            Color::new(0.2, 0.4, 0.6, 1.0),
+            Color::new(0.9, 0.1, 0.5, 0.5),
+            Color::new(0.3, 0.8, 0.1, 1.0),
+            Color::GRAY,
+        ];
+        for color in colors {
+            let (h, s, v) = color.to_hsv();
+            assert_close(Color::from_hsv(h, s, v, color.a), color);
+            let (h, s, l) = color.to_hsl();
+            assert_close(Color::from_hsl(h, s, l, color.a), color);
+        }
+    }
+}
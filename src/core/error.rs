@@ -0,0 +1,71 @@
+use std::{error::Error, fmt::Display};
+
+use wgpu::{Device, ErrorFilter, SurfaceError};
+
+/// Errors that can happen while creating GPU resources or rendering.
+#[derive(Debug)]
+pub enum LError {
+    /// Window surface was lost or became outdated and has been reconfigured. The frame should be
+    /// skipped and drawn again.
+    SurfaceLost,
+    /// Acquiring the next frame from the surface took too long.
+    Timeout,
+    /// There's not enough GPU memory left.
+    OutOfMemory,
+    /// GPU buffer could not be created.
+    BufferCreation(String),
+    /// Shader could not be compiled or linked with the pipeline.
+    ShaderCompile(String),
+    /// GPU commands were not valid.
+    Validation(String),
+    /// Asset file could not be read or parsed.
+    AssetLoad(String),
+}
+impl Display for LError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LError::SurfaceLost => f.write_str("Window surface was lost."),
+            LError::Timeout => f.write_str("Timed out acquiring the next frame."),
+            LError::OutOfMemory => f.write_str("GPU is out of memory."),
+            LError::BufferCreation(e) => write!(f, "Error creating buffer: {}", e),
+            LError::ShaderCompile(e) => write!(f, "Error compiling shader: {}", e),
+            LError::Validation(e) => write!(f, "Validation error: {}", e),
+            LError::AssetLoad(e) => write!(f, "Error loading asset: {}", e),
+        }
+    }
+}
+impl LError {
+    /// Wrap a GPU error, using `kind` for anything other than running out of memory.
+    pub(crate) fn from_gpu(error: wgpu::Error, kind: fn(String) -> LError) -> Self {
+        match error {
+            wgpu::Error::OutOfMemory { .. } => LError::OutOfMemory,
+            error => kind(error.to_string()),
+        }
+    }
+}
+impl Error for LError {
+}
+impl From<SurfaceError> for LError {
+    fn from(val: SurfaceError) -> Self {
+        match val {
+            SurfaceError::Lost | SurfaceError::Outdated => LError::SurfaceLost,
+            SurfaceError::Timeout => LError::Timeout,
+            SurfaceError::OutOfMemory => LError::OutOfMemory,
+        }
+    }
+}
+
+/// Run GPU commands and return the first out of memory or validation error they caused.
+pub(crate) fn catch_gpu_error<T>(
+    device: &Device, commands: impl FnOnce() -> T,
+) -> Result<T, wgpu::Error> {
+    device.push_error_scope(ErrorFilter::OutOfMemory);
+    device.push_error_scope(ErrorFilter::Validation);
+    let result = commands();
+    let validation = pollster::block_on(device.pop_error_scope());
+    let memory = pollster::block_on(device.pop_error_scope());
+    match memory.or(validation) {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::{HashMap, HashSet};
+
+use gilrs::EventType;
+pub use gilrs::{Axis, Button, GamepadId};
+
+/// Button and axis state of a connected gamepad, updated by the application loop.
+#[derive(Default, Debug)]
+pub struct GamepadInput {
+    buttons_down: HashSet<Button>,
+    buttons_pressed: HashSet<Button>,
+    buttons_released: HashSet<Button>,
+    axes: HashMap<Axis, f32>,
+}
+
+impl GamepadInput {
+    /// Returns if the button is currently being held down.
+    pub fn is_button_down(&self, button: Button) -> bool { self.buttons_down.contains(&button) }
+
+    /// Returns if the button has been pressed since last frame.
+    pub fn was_button_pressed(&self, button: Button) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Returns if the button has been released since last frame.
+    pub fn was_button_released(&self, button: Button) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// Current value of the axis, from -1 to 1 for sticks. Axes that haven't moved yet return 0.
+    pub fn axis_value(&self, axis: Axis) -> f32 { self.axes.get(&axis).copied().unwrap_or(0.0) }
+
+    pub(crate) fn process(&mut self, event: &EventType) {
+        match event {
+            EventType::ButtonPressed(button, _) => {
+                if self.buttons_down.insert(*button) {
+                    self.buttons_pressed.insert(*button);
+                }
+            }
+            EventType::ButtonReleased(button, _) => {
+                self.buttons_down.remove(button);
+                self.buttons_released.insert(*button);
+            }
+            EventType::AxisChanged(axis, value, _) => {
+                self.axes.insert(*axis, *value);
+            }
+            _ => {}
+        }
+    }
+
+    /// Clear events that only last for a single frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+#[cfg(feature = "gamepad")]
+use std::collections::HashMap;
+
+use glam::Vec2;
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+#[cfg(feature = "gamepad")]
+use crate::{GamepadId, GamepadInput};
+
+/// Keyboard and mouse state for a window, updated by the application loop.
+#[derive(Default, Debug)]
+pub struct Input {
+    keys_down: HashSet<VirtualKeyCode>,
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+    mouse_position: Vec2,
+    mouse_delta: Vec2,
+    mouse_down: HashSet<MouseButton>,
+    scroll_delta: f32,
+    #[cfg(feature = "gamepad")]
+    gamepads: HashMap<GamepadId, GamepadInput>,
+}
+
+impl Input {
+    /// Returns if the key is currently being held down.
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool { self.keys_down.contains(&key) }
+
+    /// Returns if the key has been pressed since last frame.
+    pub fn was_key_pressed(&self, key: VirtualKeyCode) -> bool { self.keys_pressed.contains(&key) }
+
+    /// Returns if the key has been released since last frame.
+    pub fn was_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    /// Cursor position in physical pixels, with the origin at the top left of the window.
+    pub fn mouse_position(&self) -> Vec2 { self.mouse_position }
+
+    /// Raw mouse movement since last frame, not limited by the window bounds and keeps updating
+    /// while the cursor is grabbed. Units depend on the platform and device, and mouse motion is
+    /// reported to every window of the application.
+    pub fn mouse_delta(&self) -> Vec2 { self.mouse_delta }
+
+    /// Returns if the mouse button is currently being held down.
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool { self.mouse_down.contains(&button) }
+
+    /// Vertical scroll since last frame. Measured in lines, or in pixels for devices with precise
+    /// scrolling like touchpads.
+    pub fn scroll_delta(&self) -> f32 { self.scroll_delta }
+
+    /// State of a connected gamepad. Gamepads connected before the application started show up
+    /// once they send their first input.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadInput> { self.gamepads.get(&id) }
+
+    /// Ids of the connected gamepads.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ { self.gamepads.keys().copied() }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn process_gamepad(&mut self, event: &gilrs::Event) {
+        match event.event {
+            gilrs::EventType::Disconnected => {
+                self.gamepads.remove(&event.id);
+            }
+            ref event_type => self
+                .gamepads
+                .entry(event.id)
+                .or_default()
+                .process(event_type),
+        }
+    }
+
+    pub(crate) fn process_cursor(&mut self, position: Vec2) { self.mouse_position = position; }
+
+    pub(crate) fn process_mouse_motion(&mut self, delta: Vec2) { self.mouse_delta += delta; }
+
+    pub(crate) fn process_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        match state {
+            ElementState::Pressed => self.mouse_down.insert(button),
+            ElementState::Released => self.mouse_down.remove(&button),
+        };
+    }
+
+    pub(crate) fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.scroll_delta += match delta {
+            MouseScrollDelta::LineDelta(_, y) => *y,
+            MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
+        };
+    }
+
+    pub(crate) fn process_keyboard(&mut self, input: &KeyboardInput) {
+        let Some(key) = input.virtual_keycode else {
+            return;
+        };
+        match input.state {
+            ElementState::Pressed => {
+                //Key repeat sends multiple presses without releasing.
+                if self.keys_down.insert(key) {
+                    self.keys_pressed.insert(key);
+                }
+            }
+            ElementState::Released => {
+                self.keys_down.remove(&key);
+                self.keys_released.insert(key);
+            }
+        }
+    }
+
+    /// Clear events that only last for a single frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.scroll_delta = 0.0;
+        self.mouse_delta = Vec2::ZERO;
+        #[cfg(feature = "gamepad")]
+        self.gamepads.values_mut().for_each(GamepadInput::end_frame);
+    }
+}
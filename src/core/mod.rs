@@ -1,11 +1,23 @@
 mod camera;
 mod color;
+mod error;
+
+#[cfg(feature = "gamepad")]
+mod gamepad;
+
+mod input;
 mod math;
 mod transform;
 mod window;
 
 pub use camera::*;
 pub use color::*;
+pub use error::*;
+
+#[cfg(feature = "gamepad")]
+pub use gamepad::*;
+
+pub use input::*;
 pub use math::*;
 pub use transform::*;
 pub use window::*;
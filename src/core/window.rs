@@ -1,43 +1,358 @@
 use std::{
+    cell::RefCell,
     error::Error,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
 use egui::Context;
-use glam::UVec2;
+use glam::{UVec2, Vec2};
 
-use winit::dpi::LogicalSize;
+use wgpu::PresentMode;
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{MouseButton, VirtualKeyCode},
+    window::{CursorGrabMode, Icon},
+};
 
-use crate::{FrameContext, RenderOptions, RenderPass, RenderPassBuilder};
+use crate::{
+    ComputePass, FrameContext, Input, LError, PendingMap, RenderOptions, RenderPass,
+    RenderPassBuilder, StatsOverlay, Texture,
+};
 
 #[cfg(feature = "egui")]
 use crate::EguiContext;
 
+#[cfg(feature = "gamepad")]
+use crate::{GamepadId, GamepadInput};
+
+/// Maximum frame time in seconds taken into account for fixed timestep updates.
+const MAX_FRAME_TIME: f64 = 0.25;
+
+/// Configuration used when creating a window.
+pub struct WindowSettings {
+    pub title: String,
+    /// Initial inner size in physical pixels. Platform default is used if `None`.
+    pub size: Option<UVec2>,
+    /// Maximum amount of frames rendered per second. Unlimited if `None`.
+    pub max_fps: Option<u32>,
+    /// Wait for the display refresh before presenting frames to avoid tearing.
+    pub vsync: bool,
+    /// Presentation mode used by the window surface, overriding `vsync` when set. If the surface
+    /// doesn't support it the closest available mode is used instead.
+    pub present_mode: Option<PresentMode>,
+    /// Key that closes the window when pressed. Disabled by default.
+    pub exit_key: Option<VirtualKeyCode>,
+    /// Seconds between each logic loop call. Logic runs once per frame if `None`.
+    pub fixed_timestep: Option<f64>,
+    /// Samples per pixel used for multisample anti-aliasing. Brushes drawing into the window need
+    /// the same `BrushDesc::sample_count`.
+    pub sample_count: u32,
+    /// Draw frame rate, frame time and frame count in the top left corner of the window, on top of
+    /// the frame submitted with `RenderPassBuilder::finish_render`.
+    pub show_stats: bool,
+    /// Use `DEPTH_STENCIL_FORMAT` for depth buffers so brushes can use `BrushDesc::stencil`,
+    /// instead of `DEPTH_FORMAT`.
+    pub stencil: bool,
+}
+impl WindowSettings {
+    pub fn builder() -> WindowSettingsBuilder { WindowSettingsBuilder::default() }
+}
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: "Tridify".to_string(),
+            size: None,
+            max_fps: Some(60),
+            vsync: true,
+            present_mode: None,
+            exit_key: None,
+            fixed_timestep: None,
+            sample_count: 1,
+            show_stats: false,
+            stencil: false,
+        }
+    }
+}
+
+/// Helper to create window settings by chaining calls.
+///
+/// #Examples
+/// ``` rust
+/// use tridify_rs::WindowSettings;
+/// let settings = WindowSettings::builder()
+///     .title("My app")
+///     .size(1280, 720)
+///     .max_fps(144)
+///     .vsync(true)
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct WindowSettingsBuilder {
+    settings: WindowSettings,
+}
+impl WindowSettingsBuilder {
+    pub fn title(mut self, title: &str) -> Self {
+        self.settings.title = title.to_string();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.settings.size = Some(UVec2::new(width, height));
+        self
+    }
+
+    pub fn max_fps(mut self, max_fps: u32) -> Self {
+        self.settings.max_fps = Some(max_fps);
+        self
+    }
+
+    /// Remove the frame rate cap.
+    pub fn unlimited_fps(mut self) -> Self {
+        self.settings.max_fps = None;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.settings.vsync = vsync;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.settings.present_mode = Some(present_mode);
+        self
+    }
+
+    pub fn exit_key(mut self, key: Option<VirtualKeyCode>) -> Self {
+        self.settings.exit_key = key;
+        self
+    }
+
+    pub fn fixed_timestep(mut self, seconds: f64) -> Self {
+        self.settings.fixed_timestep = Some(seconds);
+        self
+    }
+
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.settings.sample_count = sample_count;
+        self
+    }
+
+    pub fn show_stats(mut self, show_stats: bool) -> Self {
+        self.settings.show_stats = show_stats;
+        self
+    }
+
+    pub fn stencil(mut self, stencil: bool) -> Self {
+        self.settings.stencil = stencil;
+        self
+    }
+
+    pub fn build(self) -> WindowSettings { self.settings }
+}
+
+/// Frame timings of a window, averaged every second.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct FrameStats {
+    /// Frames rendered per second.
+    pub fps: f64,
+    /// Average time between frames in milliseconds.
+    pub frame_time_ms: f64,
+    /// Frames rendered since the window was created.
+    pub frame_count: u64,
+    frames: u32,
+    timer: f64,
+}
+impl FrameStats {
+    /// Register a new frame, returning if the averages have been updated.
+    fn record(&mut self, delta_time: f64) -> bool {
+        self.frame_count += 1;
+        self.frames += 1;
+        self.timer += delta_time;
+        if self.timer < 1.0 {
+            return false;
+        }
+        self.fps = self.frames as f64 / self.timer;
+        self.frame_time_ms = self.timer * 1000.0 / self.frames as f64;
+        self.frames = 0;
+        self.timer = 0.0;
+        true
+    }
+}
+
 /// Desktop window representation. Stores it's own GPU context and render loop.
 pub struct Window {
+    pub(crate) settings: WindowSettings,
     pub(crate) ctx: GpuCtx,
-    pub(crate) user_loop: Option<Box<dyn FnMut(&mut GpuCtx, &FrameContext)>>,
+    pub(crate) user_loop: Option<Box<dyn FnMut(&mut GpuCtx, &FrameContext) -> Result<(), LError>>>,
+    pub(crate) logic_loop: Option<Box<dyn FnMut(&mut GpuCtx, &FrameContext)>>,
+    pub(crate) logic_accumulator: f64,
+    pub(crate) stats: FrameStats,
 }
 impl Window {
-    /// Step through render loop once.
-    pub fn render_step(&mut self, frame_ctx: &FrameContext) {
+    /// Step through logic and render loops once. With a fixed timestep the logic loop is called as
+    /// many times as needed to catch up with the frame time.
+    ///
+    /// Frames failing to render are skipped, only running out of GPU memory is returned as a fatal
+    /// error.
+    pub fn render_step(&mut self, frame_ctx: &FrameContext) -> Result<(), LError> {
+        if self.stats.record(frame_ctx.delta_time) {
+            self.update_stats_overlay();
+        }
+        let mut render_ctx = frame_ctx.clone();
+        if let Some(logic_loop) = self.logic_loop.as_mut() {
+            match self.settings.fixed_timestep {
+                Some(step) if step > 0.0 => {
+                    //Clamp frame time so a stalled frame doesn't need endless steps to catch up.
+                    self.logic_accumulator += frame_ctx.delta_time.min(MAX_FRAME_TIME);
+                    let mut logic_ctx = frame_ctx.clone();
+                    logic_ctx.delta_time = step;
+                    while self.logic_accumulator >= step {
+                        logic_loop.as_mut()(&mut self.ctx, &logic_ctx);
+                        self.logic_accumulator -= step;
+                    }
+                    render_ctx.interpolation = self.logic_accumulator / step;
+                }
+                _ => logic_loop.as_mut()(&mut self.ctx, frame_ctx),
+            }
+        }
         if let Some(user_loop) = self.user_loop.as_mut() {
-            user_loop.as_mut()(&mut self.ctx, frame_ctx);
+            let result = user_loop.as_mut()(&mut self.ctx, &render_ctx);
+            match result {
+                //Surface has already been reconfigured, draw again on next frame.
+                Ok(()) | Err(LError::SurfaceLost) => {}
+                Err(LError::OutOfMemory) => {
+                    self.ctx.input.end_frame();
+                    return result;
+                }
+                Err(e) => {
+                    log::warn!("Dropped frame with error: {}", e);
+                }
+            }
         }
+        self.ctx.input.end_frame();
+        Ok(())
+    }
+
+    /// Frame timings of the window.
+    pub fn stats(&self) -> &FrameStats { &self.stats }
+
+    /// Change the window title.
+    pub fn set_title(&mut self, title: &str) {
+        self.settings.title = title.to_string();
+        self.ctx.set_title(title);
+    }
+
+    /// Change the window icon. See `GpuCtx::set_icon`.
+    pub fn set_icon(&self, rgba: &[u8], size: UVec2) -> Result<(), Box<dyn Error>> {
+        self.ctx.set_icon(rgba, size)
     }
 
-    /// Define closure that will be called each time the window is rendered
-    pub fn set_render_loop(&mut self, func: impl FnMut(&mut GpuCtx, &FrameContext) + 'static) {
+    /// Show or hide frame stats drawn in the top left corner of the window.
+    pub fn set_show_stats(&mut self, show_stats: bool) {
+        self.settings.show_stats = show_stats;
+        let mut overlay = self.ctx.stats_overlay.borrow_mut();
+        if !show_stats {
+            *overlay = None;
+        } else if overlay.is_none() {
+            *overlay = Some(StatsOverlay::default());
+            drop(overlay);
+            self.update_stats_overlay();
+        }
+    }
+
+    fn update_stats_overlay(&self) {
+        if let Some(overlay) = self.ctx.stats_overlay.borrow_mut().as_mut() {
+            overlay.text = format!(
+                "{:.0} FPS\n{:.2} ms\nFrame {}",
+                self.stats.fps, self.stats.frame_time_ms, self.stats.frame_count
+            );
+        }
+    }
+
+    /// Define closure that will be called each time the window is rendered. Errors returned drop
+    /// the current frame.
+    pub fn set_render_loop(
+        &mut self, func: impl FnMut(&mut GpuCtx, &FrameContext) -> Result<(), LError> + 'static,
+    ) {
         self.user_loop = Some(Box::new(func));
     }
 
+    /// Define closure used to update application logic. It's called before rendering each frame,
+    /// or at a fixed rate if `WindowSettings::fixed_timestep` is set.
+    pub fn set_logic_loop(&mut self, func: impl FnMut(&mut GpuCtx, &FrameContext) + 'static) {
+        self.logic_loop = Some(Box::new(func));
+    }
+
+    /// Returns if the key is currently being held down.
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool { self.ctx.input.is_key_down(key) }
+
+    /// Returns if the key has been pressed since last frame.
+    pub fn was_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.ctx.input.was_key_pressed(key)
+    }
+
+    /// Returns if the key has been released since last frame.
+    pub fn was_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.ctx.input.was_key_released(key)
+    }
+
+    /// Cursor position in physical pixels, with the origin at the top left of the window.
+    pub fn mouse_position(&self) -> Vec2 { self.ctx.input.mouse_position() }
+
+    /// Raw mouse movement since last frame. See `Input::mouse_delta`.
+    pub fn mouse_delta(&self) -> Vec2 { self.ctx.input.mouse_delta() }
+
+    /// Returns if the mouse button is currently being held down.
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.ctx.input.is_mouse_down(button)
+    }
+
+    /// Show or hide the cursor while it's over the window.
+    pub fn set_cursor_visible(&self, visible: bool) { self.ctx.set_cursor_visible(visible) }
+
+    /// Confine or lock the cursor to the window. See `GpuCtx::set_cursor_grab`.
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), Box<dyn Error>> {
+        self.ctx.set_cursor_grab(mode)
+    }
+
+    /// Move the cursor to a position in physical pixels relative to the top left of the window.
+    pub fn set_cursor_position(&self, position: Vec2) -> Result<(), Box<dyn Error>> {
+        self.ctx.set_cursor_position(position)
+    }
+
+    /// State of a connected gamepad. See `Input::gamepad`.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadInput> { self.ctx.input.gamepad(id) }
+
+    /// Vertical scroll since last frame.
+    pub fn scroll_delta(&self) -> f32 { self.ctx.input.scroll_delta() }
+
+    /// Draw every brush as wireframe. See `GpuCtx::set_debug_wireframe`.
+    pub fn set_debug_wireframe(&mut self, enabled: bool) { self.ctx.set_debug_wireframe(enabled); }
+
+    /// Save the next frame as an image. See `GpuCtx::capture_frame`.
+    pub fn capture_frame(&self, path: &Path) { self.ctx.capture_frame(path); }
+
+    /// Close the window. See `GpuCtx::request_close`.
+    pub fn request_close(&mut self) { self.ctx.request_close(); }
+
+    /// Window size in physical pixels.
+    pub fn size(&self) -> UVec2 { self.ctx.get_wnd_size() }
+
+    /// Ratio between physical and logical pixels. See `GpuCtx::scale_factor`.
+    pub fn scale_factor(&self) -> f64 { self.ctx.scale_factor() }
+
+    /// Window size in logical pixels.
+    pub fn logical_size(&self) -> Vec2 { self.ctx.get_wnd_logical_size() }
+
     pub fn ctx(&self) -> &GpuCtx { &self.ctx }
     pub fn view_mut(&mut self) -> &mut GpuCtx { &mut self.ctx }
 
     #[cfg(feature = "egui")]
     pub fn init_egui(&mut self) {
         if self.ctx.egui.is_some() {
-            eprintln!("Egui has already been initialized.");
+            log::warn!("Egui has already been initialized.");
             return;
         }
         self.ctx.egui = Some(EguiContext::new(&self.ctx));
@@ -49,10 +364,21 @@ impl Window {
 pub struct GpuCtx {
     pub(crate) created_time: Instant,
     pub(crate) last_draw_time: Instant,
+    pub(crate) last_frame_start: Instant,
+    pub(crate) close_requested: bool,
+    pub(crate) debug_wireframe: bool,
+    pub(crate) pending_maps: RefCell<Vec<PendingMap>>,
+    pub(crate) capture_path: RefCell<Option<PathBuf>>,
+    pub(crate) stats_overlay: RefCell<Option<StatsOverlay>>,
 
     pub(crate) winit_wnd: winit::window::Window,
+    pub(crate) input: Input,
 
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
+    pub(crate) sample_count: u32,
+    pub(crate) msaa_texture: Option<wgpu::Texture>,
+    pub(crate) depth_format: wgpu::TextureFormat,
+    pub(crate) depth_texture: wgpu::Texture,
     pub(crate) surface: wgpu::Surface,
     pub(crate) adapter: wgpu::Adapter,
     pub(crate) device: wgpu::Device,
@@ -74,24 +400,180 @@ impl GpuCtx {
         UVec2::new(size.width, size.height)
     }
 
+    /// Ratio between physical pixels and logical pixels of the monitor the window is in, such as
+    /// 2.0 on most HiDPI screens.
+    pub fn scale_factor(&self) -> f64 { self.winit_wnd.scale_factor() }
+
+    /// Window size in logical pixels, the physical size divided by the scale factor.
+    pub fn get_wnd_logical_size(&self) -> Vec2 {
+        self.get_wnd_size().as_vec2() / self.scale_factor() as f32
+    }
+
     /// Change window GPU surface dimension.
     pub fn set_wnd_gpu_size(&mut self, size: UVec2) {
         self.surface_config.width = size.x.max(1);
         self.surface_config.height = size.y.max(1);
+        log::debug!(
+            "Reconfiguring surface to {}x{}",
+            self.surface_config.width,
+            self.surface_config.height
+        );
         self.surface.configure(&self.device, &self.surface_config);
+        let size = UVec2::new(self.surface_config.width, self.surface_config.height);
+        self.msaa_texture = create_msaa_texture(
+            &self.device,
+            size,
+            self.surface_config.format,
+            self.sample_count,
+        );
+        self.depth_texture =
+            create_depth_texture(&self.device, size, self.depth_format, self.sample_count);
         self.redraw();
     }
 
+    /// Format of the depth buffers used by the window and texture render targets, either
+    /// `DEPTH_FORMAT` or `DEPTH_STENCIL_FORMAT` when created with `WindowSettings::stencil`.
+    pub fn depth_format(&self) -> wgpu::TextureFormat { self.depth_format }
+
+    /// Samples per pixel used when rendering into the window.
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+
+    /// Returns if the texture format can be multisampled with the given amount of samples.
+    pub fn supports_sample_count(&self, format: wgpu::TextureFormat, sample_count: u32) -> bool {
+        sample_count_supported(&self.adapter, &self.device, format, sample_count)
+    }
+
+    /// Device features enabled for the window, as requested with `TridifyOptions::features`.
+    pub fn features(&self) -> wgpu::Features { self.device.features() }
+
+    /// Device limits granted for the window.
+    pub fn limits(&self) -> wgpu::Limits { self.device.limits() }
+
+    /// Raw wgpu device, as an escape hatch for features not wrapped yet. Advanced and unstable:
+    /// resources created with it are not tracked by the crate and the handle may change with wgpu
+    /// updates.
+    pub fn device(&self) -> &wgpu::Device { &self.device }
+
+    /// Raw wgpu queue. Advanced and unstable, see `GpuCtx::device`.
+    pub fn queue(&self) -> &wgpu::Queue { &self.queue }
+
+    /// Raw wgpu adapter. Advanced and unstable, see `GpuCtx::device`.
+    pub fn adapter(&self) -> &wgpu::Adapter { &self.adapter }
+
+    /// Raw wgpu surface of the window. Advanced and unstable, see `GpuCtx::device`. The surface is
+    /// reconfigured by the crate when the window changes, so configuring it manually can be undone.
+    pub fn surface(&self) -> &wgpu::Surface { &self.surface }
+
+    /// Change the window title.
+    pub fn set_title(&self, title: &str) { self.winit_wnd.set_title(title); }
+
+    /// Change the window icon given its RGBA8 pixels and size. Fails if the data doesn't match the
+    /// size. Does nothing on platforms without window icons, like macOS and Wayland.
+    pub fn set_icon(&self, rgba: &[u8], size: UVec2) -> Result<(), Box<dyn Error>> {
+        let icon = Icon::from_rgba(rgba.to_vec(), size.x, size.y)?;
+        self.winit_wnd.set_window_icon(Some(icon));
+        Ok(())
+    }
+
+    /// Show or hide the cursor while it's over the window.
+    pub fn set_cursor_visible(&self, visible: bool) { self.winit_wnd.set_cursor_visible(visible); }
+
+    /// Confine the cursor to the window or lock it in place, for example for first person
+    /// cameras reading `Input::mouse_delta`. Fails if the platform doesn't support the mode:
+    /// `Confined` isn't available on macOS and `Locked` isn't available on Windows and X11.
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), Box<dyn Error>> {
+        self.winit_wnd.set_cursor_grab(mode)?;
+        Ok(())
+    }
+
+    /// Move the cursor to a position in physical pixels relative to the top left of the window.
+    /// Fails on platforms that don't allow it, like Wayland.
+    pub fn set_cursor_position(&self, position: Vec2) -> Result<(), Box<dyn Error>> {
+        self.winit_wnd
+            .set_cursor_position(PhysicalPosition::new(position.x, position.y))?;
+        Ok(())
+    }
+
+    /// Close the window once the current event loop iteration finishes, exiting the application if
+    /// it was the last one. Render and logic loops won't be called again for this window, so any
+    /// final work should be done before or right after requesting it.
+    pub fn request_close(&mut self) { self.close_requested = true; }
+
+    /// Returns if the window has been requested to close with `request_close`.
+    pub fn close_requested(&self) -> bool { self.close_requested }
+
+    /// Draw every brush as wireframe, overriding their polygon mode, to debug geometry. Requires
+    /// the `POLYGON_MODE_LINE` device feature, requested with `TridifyOptions::features`. Without
+    /// it a warning is logged and nothing changes.
+    pub fn set_debug_wireframe(&mut self, enabled: bool) {
+        if enabled
+            && !self
+                .device
+                .features()
+                .contains(wgpu::Features::POLYGON_MODE_LINE)
+        {
+            log::warn!("Debug wireframe requires device feature POLYGON_MODE_LINE.");
+            return;
+        }
+        self.debug_wireframe = enabled;
+    }
+
+    /// Returns if brushes are drawn as wireframe.
+    pub fn debug_wireframe(&self) -> bool { self.debug_wireframe }
+
+    /// Run the callbacks of buffers mapped with `GpuBuffer::map_async` that have finished, without
+    /// blocking. Done automatically every frame before rendering.
+    pub fn poll_buffer_maps(&self) {
+        let pending = std::mem::take(&mut *self.pending_maps.borrow_mut());
+        if pending.is_empty() {
+            return;
+        }
+        self.device.poll(wgpu::Maintain::Poll);
+        let mut remaining = pending
+            .into_iter()
+            .filter_map(PendingMap::try_finish)
+            .collect::<Vec<_>>();
+        //Callbacks may have started mapping other buffers.
+        remaining.append(&mut self.pending_maps.borrow_mut());
+        *self.pending_maps.borrow_mut() = remaining;
+    }
+
+    /// Block until the GPU has finished all the work submitted so far, for example by
+    /// `RenderPassBuilder::finish_render` or `ComputePass::finish`. Readbacks such as
+    /// `Texture::read_pixels` already wait, this is useful to measure or synchronize work without
+    /// reading anything back.
+    pub fn wait_idle(&self) { self.device.poll(wgpu::Maintain::Wait); }
+
+    /// Save the next frame drawn into the window as an image once it's finished, with the file
+    /// format chosen by the path extension. Errors saving it are logged. Unless the surface is
+    /// configured to be copied from, that frame is drawn into an intermediate texture which is then
+    /// copied into the surface, since not every backend allows reading surfaces.
+    pub fn capture_frame(&self, path: &Path) {
+        *self.capture_path.borrow_mut() = Some(path.to_path_buf());
+    }
+
     /// Force the window to render again.
     pub fn redraw(&self) { self.winit_wnd.request_redraw(); }
 
     /// Create a new frame that will be drawn to.
-    pub fn create_render_builder(&self) -> RenderPassBuilder {
-        RenderPassBuilder::new(self).expect(
-            "Issue creating render pass builder. Make sure rendering cycle is being done properly.",
-        )
+    pub fn create_render_builder(&self) -> Result<RenderPassBuilder, LError> {
+        RenderPassBuilder::new(self)
     }
 
+    /// Create a render builder drawing into the texture instead of the window. See
+    /// `RenderPassBuilder::from_texture`.
+    pub fn create_texture_render_builder(
+        &self, target: &Texture,
+    ) -> Result<RenderPassBuilder, LError> {
+        RenderPassBuilder::from_texture(self, target)
+    }
+
+    /// Start recording compute shader dispatches.
+    pub fn start_compute_pass(&self) -> ComputePass { ComputePass::new(self) }
+
+    /// Keyboard and mouse state of the window, can be used inside the render loop.
+    pub fn input(&self) -> &Input { &self.input }
+
     /// Time the window has been running since its creation.
     pub fn time_running(&self) -> Duration { self.created_time.elapsed() }
 
@@ -111,3 +593,61 @@ impl GpuCtx {
             .start(dt);
     }
 }
+
+/// Create depth buffer with the size of the target it will be used with.
+pub(crate) fn create_depth_texture(
+    device: &wgpu::Device, size: UVec2, format: wgpu::TextureFormat, sample_count: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth texture"),
+        size: wgpu::Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+/// Create multisampled color texture matching the target it resolves into, or `None` without
+/// multisampling.
+pub(crate) fn create_msaa_texture(
+    device: &wgpu::Device, size: UVec2, format: wgpu::TextureFormat, sample_count: u32,
+) -> Option<wgpu::Texture> {
+    if sample_count <= 1 {
+        return None;
+    }
+    Some(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Multisampled frame texture"),
+        size: wgpu::Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }))
+}
+
+pub(crate) fn sample_count_supported(
+    adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32,
+) -> bool {
+    let features = if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(device.features())
+    };
+    features.flags.sample_count_supported(sample_count)
+}
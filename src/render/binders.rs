@@ -1,4 +1,8 @@
-use std::{borrow::Borrow, collections::HashMap};
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use wgpu::{
     BindGroup, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
@@ -6,10 +10,24 @@ use wgpu::{
 
 use crate::GpuCtx;
 
+/// Identity of a GPU resource, shared by all handles pointing to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(u64);
+impl ResourceId {
+    /// Create an id different from any other created before.
+    pub fn unique() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
 /// Provides wgpu binding data for shaders.
 pub trait ToBinder {
     fn get_layout(&self, index: u32) -> BindGroupLayoutEntry;
     fn get_group(&self, index: u32) -> BindGroupEntry;
+    /// Identity of the GPU resource bound, used to reuse bind groups created with the same
+    /// resources. Defaults to a new id every call, which never reuses bind groups.
+    fn resource_id(&self) -> ResourceId { ResourceId::unique() }
     fn debug_name(&self) -> &'static str;
 }
 impl std::fmt::Debug for dyn ToBinder {
@@ -34,6 +52,42 @@ impl Binder {
         self.bindings.insert(index, binding);
     }
 
+    /// Layout entries of every binding, sorted by location.
+    pub(crate) fn layout_entries(&self) -> Vec<BindGroupLayoutEntry> {
+        let mut entries = self
+            .bindings
+            .iter()
+            .map(|(id, to_bind)| to_bind.get_layout(*id))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.binding);
+        entries
+    }
+
+    /// Identity of every resource bound with its location, sorted by location.
+    pub(crate) fn resource_key(&self) -> Vec<(u32, ResourceId)> {
+        let mut key = self
+            .bindings
+            .iter()
+            .map(|(id, to_bind)| (*id, to_bind.resource_id()))
+            .collect::<Vec<_>>();
+        key.sort_by_key(|(id, _)| *id);
+        key
+    }
+
+    /// Create a bind group with the bound resources using an existing layout.
+    pub(crate) fn create_group(&self, gpu: &GpuCtx, layout: &BindGroupLayout) -> BindGroup {
+        let entries = self
+            .bindings
+            .iter()
+            .map(|(id, to_bind)| to_bind.get_group(*id))
+            .collect::<Vec<_>>();
+        gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &entries,
+            label: None,
+        })
+    }
+
     /// Create GPU bindings to link with the render pipeline.
     pub fn bake(&self, wnd: &GpuCtx) -> (BindGroupLayout, BindGroup) {
         let layout_entries = self
@@ -45,7 +99,7 @@ impl Binder {
         for (i, bind) in self.bindings.iter() {
             let cell = bind.as_ref();
             let borrowed_tobind = cell.borrow();
-            println!("Loc: {:?} - Bind: {:?}", i, bind);
+            log::trace!("Loc: {:?} - Bind: {:?}", i, bind);
             bind_entries.push((*i, borrowed_tobind));
         }
         let groups = bind_entries
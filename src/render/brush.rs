@@ -1,12 +1,32 @@
-use std::{borrow::Cow, collections::HashMap, error::Error, fs::File, io::Read, path::Path};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use wgpu::{
-    BindGroup, BlendState, ColorTargetState, FragmentState, MultisampleState,
-    PipelineLayoutDescriptor, PrimitiveState, RenderPipeline, RenderPipelineDescriptor,
-    ShaderModule, ShaderModuleDescriptor, VertexState,
+    BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BlendState,
+    ColorTargetState, CompareFunction, DepthBiasState, DepthStencilState, Face, Features,
+    FragmentState, FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode,
+    PrimitiveState, PrimitiveTopology, PushConstantRange, RenderPipeline, RenderPipelineDescriptor,
+    ShaderModule, ShaderModuleDescriptor, ShaderStages, StencilState, TextureFormat,
+    VertexBufferLayout, VertexState,
+};
+
+use crate::{
+    catch_gpu_error, Binder, GpuCtx, Instance, LError, ResourceId, ShaderBindings, ToBinder,
+    Vertex, DEPTH_STENCIL_FORMAT,
 };
 
-use crate::{Binder, GpuCtx, ToBinder, Vertex};
+/// Shader stages push constants of brushes are visible to.
+pub const PUSH_CONSTANT_STAGES: ShaderStages = ShaderStages::VERTEX_FRAGMENT;
+
+/// Bind groups kept alive by a brush before the ones not currently bound are dropped.
+const MAX_CACHED_BIND_GROUPS: usize = 32;
 
 pub enum AlphaBlend {
     Default,
@@ -47,36 +67,133 @@ impl From<AlphaBlend> for wgpu::BlendComponent {
     }
 }
 
+/// How the colors output by the brush are combined with the ones already in the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    /// Output replaces the target color.
+    Opaque,
+    /// Output is blended with the target based on its alpha.
+    AlphaBlend,
+    /// Output is added on top of the target.
+    Additive,
+    /// Output is multiplied with the target.
+    Multiply,
+    /// Custom wgpu blend state.
+    Custom(BlendState),
+}
+impl From<BlendMode> for BlendState {
+    fn from(val: BlendMode) -> Self {
+        match val {
+            BlendMode::Opaque => BlendState::REPLACE,
+            BlendMode::AlphaBlend => BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => BlendState {
+                color: AlphaBlend::Additive.into(),
+                alpha: AlphaBlend::Additive.into(),
+            },
+            BlendMode::Multiply => BlendState {
+                color: AlphaBlend::Multiplied.into(),
+                alpha: AlphaBlend::Multiplied.into(),
+            },
+            BlendMode::Custom(state) => state,
+        }
+    }
+}
+
+/// Pipeline configuration used when creating a brush.
+///
+/// By default shapes are drawn opaque as filled triangle lists, with counter clockwise triangles
+/// facing front, back faces culled and no depth testing.
 pub struct BrushDesc {
-    pub blend: wgpu::BlendState,
+    pub blend: BlendMode,
+    /// How indices are assembled into primitives. Line topologies are always drawn 1 pixel wide
+    /// by wgpu, use `ShapeBatch::add_line` or `add_polyline` with triangles for wider lines.
+    pub topology: PrimitiveTopology,
+    /// Modes other than `Fill` require the `POLYGON_MODE_LINE` or `POLYGON_MODE_POINT` device
+    /// features, requested with `TridifyOptions::features`.
+    pub polygon_mode: PolygonMode,
+    /// Faces to discard. Use `None` to draw double sided geometry.
+    pub cull_mode: Option<Face>,
+    /// Winding order of front facing triangles.
+    pub front_face: FrontFace,
+    /// Discard fragments behind the ones already drawn. Requires `RenderOptions::depth`.
+    pub depth_test: bool,
+    /// Write fragment depth into the depth buffer. Requires `RenderOptions::depth`.
+    pub depth_write: bool,
+    /// Stencil test and operations, disabled by default. Requires a window created with
+    /// `WindowSettings::stencil` and `RenderOptions::depth`. The reference value is set with
+    /// `RenderPass::set_stencil_reference`.
+    pub stencil: StencilState,
+    /// Constant added to the fragment depth, in units of the smallest depth difference. Negative
+    /// values move the shape towards the camera, to draw decals or outlines over coplanar faces
+    /// without z-fighting. Only used with `depth_test` or `depth_write`.
+    pub depth_bias: i32,
+    /// Bias scaled by the depth slope of the triangle, so faces seen at steep angles are moved more.
+    pub depth_bias_slope_scale: f32,
+    /// Maximum bias applied, or 0 for no limit.
+    pub depth_bias_clamp: f32,
+    /// Samples per pixel, must match the sample count of the target it draws into.
+    pub sample_count: u32,
+    /// Read per instance data from a second vertex buffer using `Instance::DESC`. Shapes drawn
+    /// with it need to be baked with `ShapeBatch::bake_instanced`.
+    pub instanced: bool,
+    /// Layout of the per instance data when `instanced` is enabled, `Instance::DESC` by default.
+    /// Other layouts such as `SpriteInstance::DESC` need shaders and instance buffers matching them.
+    pub instance_layout: VertexBufferLayout<'static>,
+    /// Formats of the color targets drawn into, written by the shader at `@location(0..n)`. Empty
+    /// uses the window surface format. Brushes drawing into textures must use the formats of those
+    /// textures, in the same order.
+    pub targets: Vec<TextureFormat>,
+    /// Size in bytes of the push constants available to the vertex and fragment stages, set with
+    /// `RenderPass::set_push_constants`. Needs to be a multiple of 4 and requires the
+    /// `PUSH_CONSTANTS` device feature when not 0.
+    pub push_constant_size: u32,
 }
 impl Default for BrushDesc {
     fn default() -> Self {
         Self {
-            blend: BlendState {
-                color: wgpu::BlendComponent {
-                    src_factor: wgpu::BlendFactor::Src,
-                    dst_factor: wgpu::BlendFactor::OneMinusSrc,
-                    operation: wgpu::BlendOperation::Add,
-                },
-                alpha: wgpu::BlendComponent {
-                    src_factor: wgpu::BlendFactor::SrcAlpha,
-                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    operation: wgpu::BlendOperation::Add,
-                },
-            },
+            blend: BlendMode::Opaque,
+            topology: PrimitiveTopology::TriangleList,
+            polygon_mode: PolygonMode::Fill,
+            cull_mode: Some(Face::Back),
+            front_face: FrontFace::Ccw,
+            depth_test: false,
+            depth_write: false,
+            stencil: StencilState::default(),
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+            sample_count: 1,
+            instanced: false,
+            instance_layout: Instance::DESC,
+            targets: Vec::new(),
+            push_constant_size: 0,
         }
     }
 }
 
 ///Used to tell the GPU how to draw the shapes provided.
+///
+///Bind groups are cached by the identity of the resources bound, so binding again the same assets
+///(or clones of them) reuses the existing bind group, and writing new contents into a bound texture
+///or buffer needs no update at all. The pipeline is only rebuilt when the layout of the bindings
+///changes, meaning a group or location is added or removed or an asset of a different kind or
+///format is bound, when the shader is reloaded or when the window debug wireframe mode changes.
+///Changing the layout drops every cached bind group.
 pub struct Brush {
     desc: BrushDesc,
+    source_path: Option<PathBuf>,
     compiled_shader: ShaderModule,
+    shader_bindings: ShaderBindings,
     cached_pipeline: Option<RenderPipeline>,
-    cached_bindings: Vec<(u32, BindGroup)>,
+    cached_layouts: HashMap<u32, (Vec<BindGroupLayoutEntry>, BindGroupLayout)>,
+    cached_groups: HashMap<(u32, Vec<(u32, ResourceId)>), Rc<BindGroup>>,
+    cached_bindings: Vec<(u32, Rc<BindGroup>)>,
     assets_to_bind: HashMap<u32, Binder>,
     needs_update: bool,
+    /// If the pipeline needs to be rebuilt on the next update.
+    pipeline_outdated: bool,
+    /// If the cached pipeline was built with the window debug wireframe override.
+    wireframe: bool,
 }
 
 impl Brush {
@@ -86,7 +203,9 @@ impl Brush {
     ) -> Result<Self, Box<dyn Error>> {
         let mut source = String::new();
         File::open(shader_path)?.read_to_string(&mut source)?;
-        Self::from_source(desc, wnd, source)
+        let mut brush = Self::from_source(desc, wnd, source)?;
+        brush.source_path = Some(shader_path.to_path_buf());
+        Ok(brush)
     }
 
     /// Create brush directly providing the shader source.
@@ -94,22 +213,105 @@ impl Brush {
         desc: BrushDesc, wnd: &GpuCtx, shader_source: String,
     ) -> Result<Self, Box<dyn Error>> {
         let device = &wnd.device;
-        let shader = device.create_shader_module(ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(shader_source.as_str())),
-        });
+        let required_feature = match desc.polygon_mode {
+            PolygonMode::Fill => Features::empty(),
+            PolygonMode::Line => Features::POLYGON_MODE_LINE,
+            PolygonMode::Point => Features::POLYGON_MODE_POINT,
+        };
+        if !device.features().contains(required_feature) {
+            return Err(format!(
+                "Polygon mode {:?} requires device feature {:?} which is not enabled.",
+                desc.polygon_mode, required_feature
+            )
+            .into());
+        }
+        if desc.push_constant_size > 0 {
+            if !device.features().contains(Features::PUSH_CONSTANTS) {
+                return Err(
+                    "Push constants require device feature PUSH_CONSTANTS which is not enabled."
+                        .into(),
+                );
+            }
+            let max_size = device.limits().max_push_constant_size;
+            if desc.push_constant_size > max_size || desc.push_constant_size % 4 != 0 {
+                return Err(format!(
+                    "Push constant size {} needs to be a multiple of 4 up to {}.",
+                    desc.push_constant_size, max_size
+                )
+                .into());
+            }
+        }
+        let formats = if desc.targets.is_empty() {
+            vec![wnd.surface_config.format]
+        } else {
+            desc.targets.clone()
+        };
+        if let Some(format) = formats
+            .iter()
+            .find(|format| !wnd.supports_sample_count(**format, desc.sample_count))
+        {
+            return Err(format!(
+                "Sample count {} is not supported with format {:?}.",
+                desc.sample_count, format
+            )
+            .into());
+        }
+        let shader = Self::compile_shader(wnd, &shader_source)?;
         Ok(Self {
             desc,
+            source_path: None,
             compiled_shader: shader,
+            shader_bindings: ShaderBindings::from_wgsl(&shader_source)?,
             assets_to_bind: HashMap::new(),
+            cached_layouts: HashMap::new(),
+            cached_groups: HashMap::new(),
             cached_bindings: Vec::new(),
             cached_pipeline: None,
             needs_update: true,
+            pipeline_outdated: true,
+            wireframe: false,
+        })
+    }
+
+    /// Read and compile again the shader from the path the brush was created with. If compilation
+    /// fails the previous shader is kept and the error is returned.
+    pub fn reload(&mut self, gpu: &GpuCtx) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .source_path
+            .as_ref()
+            .ok_or("Brush was not created from a shader path.")?;
+        let mut source = String::new();
+        File::open(path)?.read_to_string(&mut source)?;
+        self.compiled_shader = Self::compile_shader(gpu, &source)?;
+        self.shader_bindings = ShaderBindings::from_wgsl(&source)?;
+        self.needs_update = true;
+        self.pipeline_outdated = true;
+        Ok(())
+    }
+
+    /// Path of the shader used to create the brush, if any.
+    pub fn source_path(&self) -> Option<&Path> { self.source_path.as_deref() }
+
+    fn compile_shader(gpu: &GpuCtx, source: &str) -> Result<ShaderModule, LError> {
+        let device = &gpu.device;
+        //Catch compilation errors here instead of when building the pipeline.
+        let shader = catch_gpu_error(device, || {
+            device.create_shader_module(ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+            })
         })
+        .map_err(|e| {
+            let e = LError::from_gpu(e, LError::ShaderCompile);
+            log::error!("{}", e);
+            e
+        })?;
+        log::debug!("Compiled shader of {} bytes", source.len());
+        Ok(shader)
     }
 
-    /// Bind asset given a group and location index. Both indices need to match with shader's or it
-    /// will panic when baking and linking with rendering pipeline.
+    /// Bind asset given a group and location index. Both indices need to match with shader's or
+    /// the next update will fail listing the bindings that don't match.
     pub fn bind(&mut self, group_index: u32, loc_index: u32, asset: impl ToBinder + 'static) {
         let asset = Box::new(asset);
         if let Some(binder) = self.assets_to_bind.get_mut(&group_index) {
@@ -125,58 +327,200 @@ impl Brush {
     /// Returns if brush has been modified and needs to update the GPU with new data.
     pub fn needs_update(&self) -> bool { self.needs_update }
 
-    /// Update GPU bindings and pipelines with current brush data.
-    pub fn update(&mut self, gpu: &GpuCtx) {
+    /// Returns if the brush needs to update, either because it was modified or because the window
+    /// debug wireframe mode changed since the pipeline was built.
+    pub(crate) fn is_outdated(&self, gpu: &GpuCtx) -> bool {
+        self.needs_update || self.wireframe != gpu.debug_wireframe
+    }
+
+    /// Update GPU bindings and pipelines with current brush data, reusing cached bind groups and
+    /// the pipeline when possible. Fails if the shader doesn't match the bindings or vertex layout,
+    /// in which case the brush will try to update again next time.
+    pub fn update(&mut self, gpu: &GpuCtx) -> Result<(), LError> {
         let device = &gpu.device;
+
+        //Check bound assets against the shader before creating anything
+        let mut layouts = self
+            .assets_to_bind
+            .iter()
+            .map(|(i, binder)| (*i, binder.layout_entries()))
+            .collect::<Vec<_>>();
+        layouts.sort_by_key(|(i, _)| *i);
+        let mismatches = layouts
+            .iter()
+            .flat_map(|(i, entries)| self.shader_bindings.mismatches(*i, entries))
+            .collect::<Vec<_>>();
+        if !mismatches.is_empty() {
+            return Err(LError::Validation(mismatches.join("\n")));
+        }
+
+        //Update layouts of groups that changed
+        let group_count = self.cached_layouts.len();
+        self.cached_layouts
+            .retain(|i, _| self.assets_to_bind.contains_key(i));
+        let mut layout_changed = self.cached_layouts.len() != group_count;
+        for (i, entries) in layouts {
+            if matches!(self.cached_layouts.get(&i), Some((cached, _)) if *cached == entries) {
+                continue;
+            }
+            let bgl = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: None,
+                entries: &entries,
+            });
+            self.cached_layouts.insert(i, (entries, bgl));
+            layout_changed = true;
+        }
+        if layout_changed {
+            self.cached_groups.clear();
+            self.pipeline_outdated = true;
+        }
+
+        //Reuse bind groups created with the same resources
         self.cached_bindings.clear();
-        let mut bgls: Vec<(u32, wgpu::BindGroupLayout)> = Vec::new();
         for (i, binder) in self.assets_to_bind.iter() {
-            //Bake group
-            let (bgl, bg) = binder.bake(gpu);
-            bgls.push((*i, bgl));
-            self.cached_bindings.push((*i, bg));
+            let layout = &self.cached_layouts[i].1;
+            let group = self
+                .cached_groups
+                .entry((*i, binder.resource_key()))
+                .or_insert_with(|| Rc::new(binder.create_group(gpu, layout)));
+            self.cached_bindings.push((*i, group.clone()));
         }
-        bgls.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        self.cached_bindings.sort_by_key(|(i, _)| *i);
+        if self.cached_groups.len() > MAX_CACHED_BIND_GROUPS {
+            self.cached_groups
+                .retain(|_, group| Rc::strong_count(group) > 1);
+        }
+
+        if !self.pipeline_outdated && self.wireframe == gpu.debug_wireframe {
+            self.needs_update = false;
+            return Ok(());
+        }
+        let mut bgls = self.cached_layouts.iter().collect::<Vec<_>>();
+        bgls.sort_by_key(|(i, _)| **i);
+        let push_constant_range = [PushConstantRange {
+            stages: PUSH_CONSTANT_STAGES,
+            range: 0..self.desc.push_constant_size,
+        }];
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &bgls.iter().map(|x| &x.1).collect::<Vec<_>>(),
-            push_constant_ranges: &[],
-        });
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &self.compiled_shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::DESC],
+            bind_group_layouts: &bgls.iter().map(|(_, (_, bgl))| bgl).collect::<Vec<_>>(),
+            push_constant_ranges: if self.desc.push_constant_size > 0 {
+                &push_constant_range
+            } else {
+                &[]
             },
-            fragment: Some(FragmentState {
-                module: &self.compiled_shader,
-                entry_point: "fs_main",
-                targets: &[Some(ColorTargetState {
+        });
+        let formats = if self.desc.targets.is_empty() {
+            vec![gpu.surface_config.format]
+        } else {
+            self.desc.targets.clone()
+        };
+        let targets = formats
+            .into_iter()
+            .map(|format| {
+                Some(ColorTargetState {
                     write_mask: wgpu::ColorWrites::ALL,
-                    format: gpu.surface.get_capabilities(&gpu.adapter).formats[0],
-                    blend: Some(self.desc.blend),
-                })],
-            }),
-            primitive: PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
+                    format,
+                    //Replacing needs no blending, which integer formats don't support.
+                    blend: (self.desc.blend != BlendMode::Opaque).then(|| self.desc.blend.into()),
+                })
+            })
+            .collect::<Vec<_>>();
+        let depth_stencil = (self.desc.depth_test
+            || self.desc.depth_write
+            || self.desc.stencil.is_enabled())
+        .then(|| DepthStencilState {
+            format: gpu.depth_format,
+            depth_write_enabled: self.desc.depth_write,
+            depth_compare: if self.desc.depth_test {
+                CompareFunction::Less
+            } else {
+                CompareFunction::Always
+            },
+            stencil: self.desc.stencil.clone(),
+            bias: DepthBiasState {
+                constant: self.desc.depth_bias,
+                slope_scale: self.desc.depth_bias_slope_scale,
+                clamp: self.desc.depth_bias_clamp,
             },
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            multiview: None,
         });
+        if self.desc.stencil.is_enabled() && gpu.depth_format != DEPTH_STENCIL_FORMAT {
+            return Err(LError::Validation(
+                "Stencil requires a window created with WindowSettings::stencil.".to_string(),
+            ));
+        }
+        let buffers = if self.desc.instanced {
+            vec![Vertex::DESC, self.desc.instance_layout.clone()]
+        } else {
+            vec![Vertex::DESC]
+        };
+        let pipeline = catch_gpu_error(device, || {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &self.compiled_shader,
+                    entry_point: "vs_main",
+                    buffers: &buffers,
+                },
+                fragment: Some(FragmentState {
+                    module: &self.compiled_shader,
+                    entry_point: "fs_main",
+                    targets: &targets,
+                }),
+                primitive: PrimitiveState {
+                    topology: self.desc.topology,
+                    //Shape buffers use 16 or 32 bit indices depending on their size, so strips
+                    //are not tied to a single format and primitive restart is not used.
+                    strip_index_format: None,
+                    front_face: self.desc.front_face,
+                    cull_mode: self.desc.cull_mode,
+                    polygon_mode: if gpu.debug_wireframe {
+                        PolygonMode::Line
+                    } else {
+                        self.desc.polygon_mode
+                    },
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil,
+                multisample: MultisampleState {
+                    count: self.desc.sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        })
+        .map_err(|e| {
+            let e = LError::from_gpu(e, LError::ShaderCompile);
+            log::error!("Error creating brush pipeline: {}", e);
+            e
+        })?;
+        log::debug!("Created brush pipeline");
         self.cached_pipeline = Some(pipeline);
         self.needs_update = false;
+        self.pipeline_outdated = false;
+        self.wireframe = gpu.debug_wireframe;
+        Ok(())
     }
 
     pub fn get_pipeline(&self) -> &RenderPipeline { self.cached_pipeline.as_ref().unwrap() }
 
-    pub fn get_bind_groups(&self) -> &Vec<(u32, BindGroup)> { &self.cached_bindings }
+    pub fn get_bind_groups(&self) -> &Vec<(u32, Rc<BindGroup>)> { &self.cached_bindings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_test_gpu;
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn broken_wgsl_returns_error() {
+        with_test_gpu(|gpu| {
+            let source = "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return 1.0 }";
+            let result = Brush::from_source(BrushDesc::default(), gpu, source.to_string());
+            assert!(result.is_err());
+        });
+    }
 }
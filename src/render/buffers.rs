@@ -1,12 +1,12 @@
-use std::error::Error;
+use std::{borrow::Cow, collections::HashMap, error::Error};
 
-use glam::{Quat, Vec3};
+use glam::{Mat3, Mat4, Quat, Vec2, Vec3};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    Buffer, BufferUsages,
+    Buffer, BufferDescriptor, BufferUsages, IndexFormat,
 };
 
-use crate::{vertex, Color, GpuCtx, Rect, Vertex};
+use crate::{catch_gpu_error, vertex, Color, GpuCtx, Instance, LError, Rect, Vertex};
 
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
@@ -22,7 +22,49 @@ impl Mesh {
 pub struct ShapeBuffer {
     pub vertex_buffer: Buffer,
     pub index_buffer: Buffer,
+    /// Amount of indices drawn, which can be lower than the index buffer capacity.
     pub index_len: u32,
+    /// Format of the indices in the index buffer, see `ShapeBatch::index_format`.
+    pub index_format: IndexFormat,
+    /// Per instance data, if the buffer was baked with instances.
+    pub instance_buffer: Option<Buffer>,
+    pub instance_len: u32,
+}
+
+impl ShapeBuffer {
+    /// Replace the buffer contents with the batch data. Existing GPU buffers are reused when the
+    /// data fits, otherwise they are reallocated with extra space to grow.
+    pub fn update(&mut self, gpu: &GpuCtx, batch: &ShapeBatch) -> Result<(), LError> {
+        let vertices: &[u8] = bytemuck::cast_slice(&batch.vertices);
+        let (index_format, indices) = batch.index_data();
+        reserve_buffer(gpu, &mut self.vertex_buffer, vertices.len() as u64)?;
+        reserve_buffer(gpu, &mut self.index_buffer, indices.len() as u64)?;
+        gpu.queue.write_buffer(&self.vertex_buffer, 0, vertices);
+        gpu.queue.write_buffer(&self.index_buffer, 0, &indices);
+        self.index_len = batch.indices.len() as u32;
+        self.index_format = index_format;
+        Ok(())
+    }
+}
+
+/// Reallocate buffer with the same usage if it's smaller than the size provided. Contents are not
+/// kept.
+pub(crate) fn reserve_buffer(gpu: &GpuCtx, buffer: &mut Buffer, size: u64) -> Result<(), LError> {
+    if buffer.size() >= size {
+        return Ok(());
+    }
+    let size = size.max(buffer.size() * 2);
+    log::debug!("Growing {:?} buffer to {} bytes", buffer.usage(), size);
+    *buffer = catch_gpu_error(&gpu.device, || {
+        gpu.device.create_buffer(&BufferDescriptor {
+            label: None,
+            size,
+            usage: buffer.usage(),
+            mapped_at_creation: false,
+        })
+    })
+    .map_err(|e| LError::from_gpu(e, LError::BufferCreation))?;
+    Ok(())
 }
 
 // ///Queue of shapes to be drawn. All shapes added to the same batch will be drawn at the same time using the same brush.
@@ -30,7 +72,6 @@ pub struct ShapeBuffer {
 pub struct ShapeBatch {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
-    pub index_id_counter: u32,
 }
 
 impl ShapeBatch {
@@ -38,50 +79,670 @@ impl ShapeBatch {
         Self {
             vertices: Vec::new(),
             indices: Vec::new(),
-            index_id_counter: 0,
+        }
+    }
+
+    ///Create an empty batch with space for the given amount of vertices and indices.
+    pub fn with_capacity(vertices: usize, indices: usize) -> Self {
+        Self {
+            vertices: Vec::with_capacity(vertices),
+            indices: Vec::with_capacity(indices),
+        }
+    }
+
+    ///Reserve space for at least the given amount of additional vertices and indices.
+    pub fn reserve(&mut self, vertices: usize, indices: usize) {
+        self.vertices.reserve(vertices);
+        self.indices.reserve(indices);
+    }
+
+    ///Remove all shapes from the batch, keeping the allocated memory to be reused.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    ///Format used for the indices when baking the batch. 16 bit indices are used when every vertex
+    ///and every index fits in them, halving the index buffer size, and 32 bit indices otherwise.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch.add_triangle([vertex!(0.0, 0.0, 0.0), vertex!(1.0, 0.0, 0.0), vertex!(0.0, 1.0, 0.0)]);
+    /// assert_eq!(batch.index_format(), wgpu::IndexFormat::Uint16);
+    /// ```
+    pub fn index_format(&self) -> IndexFormat {
+        //Indices are checked too, so out of bounds ones are never truncated into valid ones.
+        if self.vertices.len() <= u16::MAX as usize
+            && self.indices.iter().all(|i| *i <= u16::MAX as u32)
+        {
+            IndexFormat::Uint16
+        } else {
+            IndexFormat::Uint32
+        }
+    }
+
+    ///Indices as bytes in the format returned by `index_format`, padded to the copy alignment.
+    fn index_data(&self) -> (IndexFormat, Cow<[u8]>) {
+        match self.index_format() {
+            IndexFormat::Uint16 => {
+                let mut indices = self.indices.iter().map(|i| *i as u16).collect::<Vec<_>>();
+                //Buffer writes need a size multiple of 4 bytes.
+                if indices.len() % 2 != 0 {
+                    indices.push(0);
+                }
+                (
+                    IndexFormat::Uint16,
+                    Cow::Owned(bytemuck::cast_slice(&indices).to_vec()),
+                )
+            }
+            IndexFormat::Uint32 => (
+                IndexFormat::Uint32,
+                Cow::Borrowed(bytemuck::cast_slice(&self.indices)),
+            ),
         }
     }
 
     ///Create buffers based on current batch data.
-    pub fn bake_buffers(&self, ctx: &GpuCtx) -> ShapeBuffer {
+    pub fn bake_buffers(&self, ctx: &GpuCtx) -> Result<ShapeBuffer, LError> {
         let device = &ctx.device;
-        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&self.vertices),
-            usage: BufferUsages::VERTEX,
-        });
-        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&self.indices),
-            usage: BufferUsages::INDEX,
-        });
-        ShapeBuffer {
+        let (index_format, indices) = self.index_data();
+        let (vertex_buffer, index_buffer) = catch_gpu_error(device, || {
+            let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&self.vertices),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            });
+            let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: &indices,
+                usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
+            });
+            (vertex_buffer, index_buffer)
+        })
+        .map_err(|e| LError::from_gpu(e, LError::BufferCreation))?;
+        log::debug!(
+            "Baked shape buffer with {} vertices and {} {:?} indices",
+            self.vertices.len(),
+            self.indices.len(),
+            index_format
+        );
+        Ok(ShapeBuffer {
             vertex_buffer,
             index_buffer,
             index_len: self.indices.len() as u32,
+            index_format,
+            instance_buffer: None,
+            instance_len: 1,
+        })
+    }
+
+    ///Create buffers based on current batch data, drawing the whole batch once per instance. Brush
+    /// used to draw it needs `BrushDesc::instanced` enabled.
+    pub fn bake_instanced(
+        &self, ctx: &GpuCtx, instances: &[Instance],
+    ) -> Result<ShapeBuffer, LError> {
+        let instance_buffer = catch_gpu_error(&ctx.device, || {
+            ctx.device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(instances),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            })
+        })
+        .map_err(|e| LError::from_gpu(e, LError::BufferCreation))?;
+        Ok(ShapeBuffer {
+            instance_buffer: Some(instance_buffer),
+            instance_len: instances.len() as u32,
+            ..self.bake_buffers(ctx)?
+        })
+    }
+
+    ///Add vertices and indices directly to the batch. Indices are relative to the vertices
+    /// provided, so they must be lower than its length. Fails without modifying the batch if any
+    /// index is out of bounds.
+    pub fn add_mesh(
+        &mut self, vertices: &[Vertex], indices: &[u32],
+    ) -> Result<&mut ShapeBatch, LError> {
+        if let Some(i) = indices.iter().find(|i| **i as usize >= vertices.len()) {
+            return Err(LError::Validation(format!(
+                "Mesh index {} out of bounds for {} vertices.",
+                i,
+                vertices.len()
+            )));
         }
+        let index = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(vertices);
+        self.indices.extend(indices.iter().map(|i| index + i));
+        Ok(self)
+    }
+
+    ///Add all shapes from another batch, offsetting its indices by the current vertex count.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut a = ShapeBatch::new();
+    /// a.add_triangle([vertex!(0.0, 0.0, 0.0), vertex!(1.0, 0.0, 0.0), vertex!(0.0, 1.0, 0.0)]);
+    /// let mut b = ShapeBatch::new();
+    /// b.add_triangle([vertex!(0.0, 1.0, 0.0), vertex!(1.0, 0.0, 0.0), vertex!(1.0, 1.0, 0.0)]);
+    /// a.extend_from(&b).append(b);
+    /// assert_eq!(a.vertices.len(), 9);
+    /// assert_eq!(a.indices, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    /// ```
+    pub fn extend_from(&mut self, other: &ShapeBatch) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| index + i));
+        self
+    }
+
+    ///Add all shapes from another batch, consuming it. Same as [`ShapeBatch::extend_from`] but
+    ///reuses the other batch memory when this one is empty.
+    pub fn append(&mut self, other: ShapeBatch) -> &mut ShapeBatch {
+        if self.vertices.is_empty() && self.indices.is_empty() {
+            *self = other;
+            return self;
+        }
+        self.extend_from(&other)
+    }
+
+    ///Multiply the position of every vertex in the batch by the matrix. Normals are transformed
+    ///so they stay perpendicular to the surface.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use glam::{Mat4, Vec3};
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch
+    ///     .add_2d_square(Vec3::ZERO, 2.0, 2.0, Color::WHITE)
+    ///     .transform(Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2));
+    /// //Bottom left corner ends up at the bottom right.
+    /// let corner = Vec3::from(batch.vertices[0].pos);
+    /// assert!(corner.abs_diff_eq(Vec3::new(1.0, -1.0, 0.0), 1e-5));
+    /// ```
+    pub fn transform(&mut self, matrix: Mat4) -> &mut ShapeBatch {
+        let normal_matrix = normal_matrix(matrix);
+        for vertex in self.vertices.iter_mut() {
+            vertex.pos = matrix.transform_point3(Vec3::from(vertex.pos)).into();
+            vertex.normal = (normal_matrix * Vec3::from(vertex.normal))
+                .normalize_or_zero()
+                .into();
+        }
+        self
     }
 
-    pub fn add_mesh(&mut self, mesh: Mesh) -> &mut ShapeBatch {
-        self.vertices.extend(&mesh.vertices);
-        self.indices.extend(&mesh.tris);
-        self.index_id_counter += mesh.vertices.len() as u32;
+    ///Add all shapes from another batch, transforming their positions and normals by the matrix.
+    pub fn add_batch_transformed(&mut self, other: &ShapeBatch, matrix: Mat4) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+        let normal_matrix = normal_matrix(matrix);
+        self.vertices.extend(other.vertices.iter().map(|v| {
+            Vertex {
+                pos: matrix.transform_point3(Vec3::from(v.pos)).into(),
+                normal: (normal_matrix * Vec3::from(v.normal))
+                    .normalize_or_zero()
+                    .into(),
+                ..*v
+            }
+        }));
+        self.indices.extend(other.indices.iter().map(|i| index + i));
+        self
+    }
+
+    ///Merge vertices with the same color, texture coordinates and normal whose positions are
+    ///within `epsilon` of each other, pointing the indices to the vertex kept. Unused vertices are
+    ///dropped. Returns the amount of vertices before and after merging.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch
+    ///     .add_triangle([vertex!(0.0, 0.0, 0.0), vertex!(1.0, 0.0, 0.0), vertex!(0.0, 1.0, 0.0)])
+    ///     .add_triangle([vertex!(0.0, 1.0, 0.0), vertex!(1.0, 0.0, 0.0), vertex!(1.0, 1.0, 0.0)]);
+    /// assert_eq!(batch.deduplicate(1e-5), (6, 4));
+    /// assert_eq!(batch.indices, [0, 1, 2, 2, 1, 3]);
+    /// ```
+    pub fn deduplicate(&mut self, epsilon: f32) -> (usize, usize) {
+        let before = self.vertices.len();
+        let epsilon = epsilon.max(f32::EPSILON);
+        let mut unique = HashMap::new();
+        let mut vertices = Vec::new();
+        for index in self.indices.iter_mut() {
+            let vertex = self.vertices[*index as usize];
+            let color = vertex.color;
+            //Positions are snapped to a grid of epsilon size, everything else needs to match.
+            let key = (
+                vertex.pos.map(|p| (p / epsilon).round() as i64),
+                [color.r, color.g, color.b, color.a].map(f32::to_bits),
+                vertex.uv.map(f32::to_bits),
+                vertex.normal.map(f32::to_bits),
+            );
+            *index = *unique.entry(key).or_insert_with(|| {
+                vertices.push(vertex);
+                vertices.len() as u32 - 1
+            });
+        }
+        self.vertices = vertices;
+        (before, self.vertices.len())
+    }
+
+    ///Replace the normal of every vertex with the average of the normals of the triangles using it,
+    ///weighted by their area. Triangles are read from the indices as a triangle list, facing
+    ///the side their vertices are counter clockwise from. Vertices not used by any triangle keep
+    ///the default normal.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch
+    ///     .add_triangle([
+    ///         vertex!(0.0, 0.0, 0.0),
+    ///         vertex!(0.0, 0.0, 1.0),
+    ///         vertex!(1.0, 0.0, 0.0),
+    ///     ])
+    ///     .recompute_normals();
+    /// //Triangle lies on the XZ plane, facing up.
+    /// assert!(batch.vertices.iter().all(|v| v.normal == [0.0, 1.0, 0.0]));
+    /// ```
+    pub fn recompute_normals(&mut self) -> &mut ShapeBatch {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+        for tri in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(self.vertices[tri[i] as usize].pos));
+            //Cross product length is twice the triangle area, weighting the normal by it.
+            let normal = (b - a).cross(c - a);
+            for i in tri {
+                normals[*i as usize] += normal;
+            }
+        }
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal
+                .try_normalize()
+                .map_or(Vertex::DEFAULT_NORMAL, Vec3::into);
+        }
         self
     }
 
     ///Add a triangle to the batch specifying its 3 vertices
     pub fn add_triangle(&mut self, v: [Vertex; 3]) -> &mut ShapeBatch {
-        let index = self.index_id_counter;
+        let index = self.vertices.len() as u32;
         self.vertices.push(v[0]);
         self.indices.push(index);
         self.vertices.push(v[1]);
         self.indices.push(index + 1);
         self.vertices.push(v[2]);
         self.indices.push(index + 2);
-        self.index_id_counter += 3;
         self
     }
 
+    ///Add a circle to the batch as a triangle fan around the center vertex. The center color is
+    ///used for the whole shape.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch.add_circle(Vertex::new(0.0, 0.0, 0.0, Some(Color::RED), None), 0.5, 32);
+    /// ```
+    pub fn add_circle(&mut self, center: Vertex, radius: f32, segments: u32) -> &mut ShapeBatch {
+        self.add_ellipse(center, radius, radius, segments)
+    }
+
+    ///Add an ellipse to the batch as a triangle fan around the center vertex, specifying the
+    ///radius on each axis. Segments are clamped to a minimum of 3.
+    pub fn add_ellipse(
+        &mut self, center: Vertex, rx: f32, ry: f32, segments: u32,
+    ) -> &mut ShapeBatch {
+        let segments = segments.max(3);
+        let ring = (0..segments).map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            Vec2::from_angle(angle) * Vec2::new(rx, ry)
+        });
+        self.add_fan(center, ring, Vec2::new(rx, ry), true)
+    }
+
+    ///Add a rectangle with rounded corners as a triangle fan around the center vertex. The corner
+    ///radius is clamped to half the smallest side and a radius of 0 adds a plain square.
+    pub fn add_rounded_rect(
+        &mut self, center: Vertex, w: f32, h: f32, corner_radius: f32, corner_segments: u32,
+    ) -> &mut ShapeBatch {
+        let hw = w / 2.0;
+        let hh = h / 2.0;
+        let radius = corner_radius.max(0.0).min(hw.abs().min(hh.abs()));
+        if radius <= 0.0 {
+            return self.add_2d_square(Vec3::from(center.pos), w, h, center.color);
+        }
+
+        let segments = corner_segments.max(1);
+        let corners = [
+            Vec2::new(hw - radius, hh - radius),
+            Vec2::new(-hw + radius, hh - radius),
+            Vec2::new(-hw + radius, -hh + radius),
+            Vec2::new(hw - radius, -hh + radius),
+        ];
+        let ring = corners.into_iter().enumerate().flat_map(|(c, corner)| {
+            (0..=segments).map(move |i| {
+                let angle = (c as f32 + i as f32 / segments as f32) * std::f32::consts::FRAC_PI_2;
+                corner + Vec2::from_angle(angle) * radius
+            })
+        });
+        self.add_fan(center, ring, Vec2::new(hw, hh), true)
+    }
+
+    ///Add a regular polygon as a triangle fan around the center vertex. `radius` is the distance
+    ///from the center to each corner and `rotation` is in radians. With no rotation the bottom edge
+    ///is horizontal, so 4 sides adds an axis aligned square. Sides are clamped to a minimum of 3.
+    pub fn add_regular_polygon(
+        &mut self, center: Vertex, radius: f32, sides: u32, rotation: f32,
+    ) -> &mut ShapeBatch {
+        let sides = sides.max(3);
+        let start = std::f32::consts::PI / sides as f32 - std::f32::consts::FRAC_PI_2;
+        let ring = (0..sides).map(|i| {
+            let angle = start + rotation + i as f32 / sides as f32 * std::f32::consts::TAU;
+            Vec2::from_angle(angle) * radius
+        });
+        self.add_fan(center, ring, Vec2::splat(radius), true)
+    }
+
+    ///Add a star as a triangle fan around the center vertex, alternating between the outer and
+    ///inner radius. `rotation` is in radians and with no rotation the first point faces up. Points
+    ///are clamped to a minimum of 3.
+    pub fn add_star(
+        &mut self, center: Vertex, outer_radius: f32, inner_radius: f32, points: u32, rotation: f32,
+    ) -> &mut ShapeBatch {
+        let points = points.max(3);
+        let ring = (0..points * 2).map(|i| {
+            let angle = std::f32::consts::FRAC_PI_2
+                + rotation
+                + i as f32 / (points * 2) as f32 * std::f32::consts::TAU;
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            Vec2::from_angle(angle) * radius
+        });
+        self.add_fan(
+            center,
+            ring,
+            Vec2::splat(outer_radius.max(inner_radius)),
+            true,
+        )
+    }
+
+    ///Add a filled circle sector as a triangle fan around the center vertex, going counter clockwise
+    ///from `start_angle` to `end_angle` in radians. If `end_angle` is smaller than `start_angle` both
+    ///angles are swapped. Segments are clamped to a minimum of 1.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch.add_pie(Vertex::new(0.0, 0.0, 0.0, Some(Color::RED), None), 0.5, 0.0, std::f32::consts::PI, 16);
+    /// assert_eq!(batch.vertices.len(), 18);
+    /// assert_eq!(batch.indices.len(), 16 * 3);
+    /// ```
+    pub fn add_pie(
+        &mut self, center: Vertex, radius: f32, start_angle: f32, end_angle: f32, segments: u32,
+    ) -> &mut ShapeBatch {
+        let (start, end) = sorted_angles(start_angle, end_angle);
+        let segments = segments.max(1);
+        let ring = (0..=segments).map(|i| {
+            let angle = start + (end - start) * i as f32 / segments as f32;
+            Vec2::from_angle(angle) * radius
+        });
+        self.add_fan(center, ring, Vec2::splat(radius), false)
+    }
+
+    ///Add a ring segment centered on `radius`, going counter clockwise from `start_angle` to
+    ///`end_angle` in radians. If `end_angle` is smaller than `start_angle` both angles are swapped.
+    ///The inner radius is clamped to 0 and segments are clamped to a minimum of 1. Arcs with no
+    ///outer radius are skipped.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch.add_arc(Vertex::new(0.0, 0.0, 0.0, Some(Color::RED), None), 0.5, 0.0, std::f32::consts::PI, 16, 0.1);
+    /// assert_eq!(batch.vertices.len(), 34);
+    /// assert_eq!(batch.indices.len(), 16 * 6);
+    /// ```
+    pub fn add_arc(
+        &mut self, center: Vertex, radius: f32, start_angle: f32, end_angle: f32, segments: u32,
+        thickness: f32,
+    ) -> &mut ShapeBatch {
+        let (start, end) = sorted_angles(start_angle, end_angle);
+        let segments = segments.max(1);
+        let outer = radius + thickness / 2.0;
+        let inner = (radius - thickness / 2.0).max(0.0);
+        if outer <= 0.0 {
+            return self;
+        }
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices
+        for i in 0..=segments {
+            let dir = Vec2::from_angle(start + (end - start) * i as f32 / segments as f32);
+            for offset in [dir * outer, dir * inner] {
+                let mut v = offset_vertex(center, offset);
+                v.uv = (Vec2::splat(0.5) + offset / (outer * 2.0)).to_array();
+                self.vertices.push(v);
+            }
+        }
+
+        //Adding indices
+        for i in 0..segments {
+            let a = index + i * 2;
+            let b = a + 2;
+            self.indices.push(a);
+            self.indices.push(b);
+            self.indices.push(a + 1);
+            self.indices.push(a + 1);
+            self.indices.push(b);
+            self.indices.push(b + 1);
+        }
+
+        self
+    }
+
+    ///Add a triangle fan around the center vertex. Ring positions are offsets from the center going
+    ///counter clockwise and `extent` is used to map them into UVs. If `closed` is true the last ring
+    ///position is also joined with the first one.
+    fn add_fan(
+        &mut self, center: Vertex, ring: impl Iterator<Item = Vec2>, extent: Vec2, closed: bool,
+    ) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+        //Axes without extent keep the center UV instead of dividing by 0.
+        let uv_scale = Vec2::select(extent.cmpeq(Vec2::ZERO), Vec2::ZERO, 0.5 / extent);
+
+        //Adding vertices
+        let mut middle = center;
+        middle.uv = [0.5, 0.5];
+        self.vertices.push(middle);
+        for offset in ring {
+            let mut v = offset_vertex(center, offset);
+            v.uv = (Vec2::splat(0.5) + offset * uv_scale).to_array();
+            self.vertices.push(v);
+        }
+
+        //Adding indices
+        let count = self.vertices.len() as u32 - index - 1;
+        let triangles = if closed {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        for i in 0..triangles {
+            self.indices.push(index);
+            self.indices.push(index + 1 + i);
+            self.indices.push(index + 1 + (i + 1) % count);
+        }
+
+        self
+    }
+
+    ///Add a line between two vertices expanded into a quad of the given thickness on the XY plane.
+    ///Zero-length lines are skipped.
+    pub fn add_line(&mut self, a: Vertex, b: Vertex, thickness: f32) -> &mut ShapeBatch {
+        let dir = Vec2::new(b.x() - a.x(), b.y() - a.y());
+        if dir.length_squared() <= f32::EPSILON {
+            return self;
+        }
+        let offset = dir.perp().normalize() * thickness / 2.0;
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices
+        self.vertices.push(offset_vertex(a, -offset));
+        self.vertices.push(offset_vertex(b, -offset));
+        self.vertices.push(offset_vertex(a, offset));
+        self.vertices.push(offset_vertex(b, offset));
+
+        //Adding indices
+        self.indices.push(index);
+        self.indices.push(index + 1);
+        self.indices.push(index + 2);
+        self.indices.push(index + 2);
+        self.indices.push(index + 1);
+        self.indices.push(index + 3);
+
+        self
+    }
+
+    ///Add a line going through all the points given on the XY plane, using miter joins between
+    ///segments. If `closed` is true the last point is also joined with the first one. Zero-length
+    ///segments are skipped.
+    pub fn add_polyline(
+        &mut self, points: &[Vertex], thickness: f32, closed: bool,
+    ) -> &mut ShapeBatch {
+        let mut points = points.to_vec();
+        points.dedup_by(|a, b| point_distance_sq(a, b) <= f32::EPSILON);
+        if closed
+            && points.len() > 2
+            && point_distance_sq(&points[0], &points[points.len() - 1]) <= f32::EPSILON
+        {
+            points.pop();
+        }
+        let count = points.len();
+        if count < 2 {
+            return self;
+        }
+
+        let half = thickness / 2.0;
+        let normal =
+            |a: &Vertex, b: &Vertex| Vec2::new(b.x() - a.x(), b.y() - a.y()).perp().normalize();
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices
+        for i in 0..count {
+            let prev = if i > 0 {
+                Some(normal(&points[i - 1], &points[i]))
+            } else if closed {
+                Some(normal(&points[count - 1], &points[0]))
+            } else {
+                None
+            };
+            let next = if i + 1 < count {
+                Some(normal(&points[i], &points[i + 1]))
+            } else if closed {
+                Some(normal(&points[count - 1], &points[0]))
+            } else {
+                None
+            };
+            let offset = match (prev, next) {
+                (Some(n0), Some(n1)) => {
+                    let miter = (n0 + n1).normalize_or_zero();
+                    let dot = miter.dot(n1);
+                    if dot <= f32::EPSILON {
+                        n1 * half
+                    } else {
+                        //Limit miter length so sharp angles don't create long spikes.
+                        miter * (half / dot).min(half * 4.0)
+                    }
+                }
+                (Some(n), None) | (None, Some(n)) => n * half,
+                (None, None) => unreachable!(),
+            };
+            self.vertices.push(offset_vertex(points[i], -offset));
+            self.vertices.push(offset_vertex(points[i], offset));
+        }
+
+        //Adding indices
+        let segments = if closed { count } else { count - 1 };
+        for i in 0..segments as u32 {
+            let a = index + i * 2;
+            let b = index + ((i + 1) % count as u32) * 2;
+            self.indices.push(a);
+            self.indices.push(b);
+            self.indices.push(a + 1);
+            self.indices.push(a + 1);
+            self.indices.push(b);
+            self.indices.push(b + 1);
+        }
+
+        self
+    }
+
+    ///Add a quadratic bezier curve on the XY plane as a thick polyline sampled at `segments` + 1
+    ///points. Color is interpolated from `p0` to `p2`. Segments are clamped to a minimum of 1.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let p = |x: f32| Vertex::new(x, 0.0, 0.0, Some(Color::WHITE), None);
+    /// let mut curve = ShapeBatch::new();
+    /// curve.add_quadratic_bezier(p(0.0), p(1.0), p(2.0), 0.1, 8);
+    /// let mut line = ShapeBatch::new();
+    /// line.add_line(p(0.0), p(2.0), 0.1);
+    ///
+    /// //A straight curve covers the same quad as a line between its end points.
+    /// let last = curve.vertices.len() - 1;
+    /// assert_eq!(curve.vertices[0].pos, line.vertices[0].pos);
+    /// assert_eq!(curve.vertices[1].pos, line.vertices[2].pos);
+    /// assert_eq!(curve.vertices[last - 1].pos, line.vertices[1].pos);
+    /// assert_eq!(curve.vertices[last].pos, line.vertices[3].pos);
+    /// assert!(curve.vertices.iter().all(|v| v.y().abs() <= 0.05 + 1e-6));
+    /// ```
+    pub fn add_quadratic_bezier(
+        &mut self, p0: Vertex, p1: Vertex, p2: Vertex, thickness: f32, segments: u32,
+    ) -> &mut ShapeBatch {
+        self.add_bezier(&[p0, p1, p2], thickness, segments)
+    }
+
+    ///Add a cubic bezier curve on the XY plane as a thick polyline sampled at `segments` + 1
+    ///points. Color is interpolated from `p0` to `p3`. Segments are clamped to a minimum of 1.
+    pub fn add_cubic_bezier(
+        &mut self, p0: Vertex, p1: Vertex, p2: Vertex, p3: Vertex, thickness: f32, segments: u32,
+    ) -> &mut ShapeBatch {
+        self.add_bezier(&[p0, p1, p2, p3], thickness, segments)
+    }
+
+    ///Sample a bezier curve with the given control points and add it as an open polyline.
+    fn add_bezier(
+        &mut self, controls: &[Vertex], thickness: f32, segments: u32,
+    ) -> &mut ShapeBatch {
+        let segments = segments.max(1);
+        let first = controls[0];
+        let last = controls[controls.len() - 1];
+        let points: Vec<Vertex> = (0..=segments)
+            .map(|i| {
+                let t = i as f32 / segments as f32;
+                //De Casteljau's algorithm, reducing the control points until one is left.
+                let mut pos: Vec<Vec3> = controls.iter().map(|v| Vec3::from(v.pos)).collect();
+                while pos.len() > 1 {
+                    pos = pos.windows(2).map(|w| w[0].lerp(w[1], t)).collect();
+                }
+                let mut v = first;
+                v.pos = pos[0].to_array();
+                v.color = first.color.lerp(last.color, t);
+                v.uv = Vec2::from(first.uv).lerp(Vec2::from(last.uv), t).to_array();
+                v
+            })
+            .collect();
+        self.add_polyline(&points, thickness, false)
+    }
+
     ///Add a square using a Rect as input
     pub fn add_rect(&mut self, rect: &Rect, color: Color) -> &mut ShapeBatch {
         self.add_2d_square(rect.center().extend(0.), rect.size.x, rect.size.y, color);
@@ -90,6 +751,8 @@ impl ShapeBatch {
 
     ///Add a square on axis XY to the batch specifying the center, width, height and color.
     pub fn add_2d_square(&mut self, center: Vec3, w: f32, h: f32, color: Color) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+
         //Adding vertices
         let hw = w / 2.0;
         let hh = h / 2.0;
@@ -103,7 +766,6 @@ impl ShapeBatch {
             .push(vertex!(center.x + hw, center.y + hh, 0., color, [1.0, 1.0]));
 
         //Adding indices
-        let index = self.index_id_counter;
         self.indices.push(index);
         self.indices.push(index + 1);
         self.indices.push(index + 2);
@@ -111,7 +773,6 @@ impl ShapeBatch {
         self.indices.push(index + 1);
         self.indices.push(index + 3);
 
-        self.index_id_counter += 4;
         self
     }
 
@@ -119,46 +780,144 @@ impl ShapeBatch {
     pub fn add_square(
         &mut self, center: Vec3, up: Vec3, normal: Vec3, w: f32, h: f32, color: Color,
     ) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+
         //Adding vertices
         let right = up.cross(normal).normalize();
         let hw = w / 2.0;
         let hh = h / 2.0;
 
-        self.vertices.push(Vertex::from_vec(
-            center - right * hw - up * hh,
-            Some(color),
-            Some([0.0, 0.0]),
-        ));
-        self.vertices.push(Vertex::from_vec(
-            center + right * hw - up * hh,
-            Some(color),
-            Some([1.0, 0.0]),
-        ));
-        self.vertices.push(Vertex::from_vec(
-            center - right * hw + up * hh,
-            Some(color),
-            Some([0.0, 1.0]),
-        ));
-        self.vertices.push(Vertex::from_vec(
-            center + right * hw + up * hh,
-            Some(color),
-            Some([1.0, 1.0]),
-        ));
+        self.vertices.push(
+            Vertex::from_vec(center - right * hw - up * hh, Some(color), Some([0.0, 0.0]))
+                .with_normal(normal),
+        );
+        self.vertices.push(
+            Vertex::from_vec(center + right * hw - up * hh, Some(color), Some([1.0, 0.0]))
+                .with_normal(normal),
+        );
+        self.vertices.push(
+            Vertex::from_vec(center - right * hw + up * hh, Some(color), Some([0.0, 1.0]))
+                .with_normal(normal),
+        );
+        self.vertices.push(
+            Vertex::from_vec(center + right * hw + up * hh, Some(color), Some([1.0, 1.0]))
+                .with_normal(normal),
+        );
 
-        //Adding indices
-        let index = self.index_id_counter;
+        //Adding indices, counter clockwise when seen from the side the normal points to.
         self.indices.push(index);
+        self.indices.push(index + 1);
+        self.indices.push(index + 2);
         self.indices.push(index + 2);
         self.indices.push(index + 1);
+        self.indices.push(index + 3);
+
+        self
+    }
+
+    ///Add a plane on the XZ plane facing up, specifying the center, size on each axis, amount of
+    ///cells per side and color. Texture coordinates go from 0 to 1 along X and Z.
+    pub fn add_plane(
+        &mut self, center: Vec3, size: Vec2, subdivisions: u32, color: Color,
+    ) -> &mut ShapeBatch {
+        let cells = subdivisions.max(1);
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices
+        for z in 0..=cells {
+            for x in 0..=cells {
+                let uv = Vec2::new(x as f32, z as f32) / cells as f32;
+                let pos = center + Vec3::new((uv.x - 0.5) * size.x, 0.0, (uv.y - 0.5) * size.y);
+                self.vertices
+                    .push(Vertex::from_vec(pos, Some(color), Some(uv.into())).with_normal(Vec3::Y));
+            }
+        }
+
+        //Adding indices
+        self.add_grid_indices(index, cells, cells);
+        self
+    }
+
+    ///Add a sphere made of rings from top to bottom split into sectors around the Y axis,
+    ///specifying the center, radius, amount of rings and sectors and color. Texture coordinates
+    ///wrap around the sphere horizontally and go from top to bottom vertically.
+    pub fn add_uv_sphere(
+        &mut self, center: Vec3, radius: f32, rings: u32, sectors: u32, color: Color,
+    ) -> &mut ShapeBatch {
+        let rings = rings.max(2);
+        let sectors = sectors.max(3);
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices. First and last sectors are repeated so texture coordinates can wrap.
+        for ring in 0..=rings {
+            let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+            for sector in 0..=sectors {
+                let phi = sector as f32 / sectors as f32 * std::f32::consts::TAU;
+                //Sectors go clockwise seen from above so triangles face outwards.
+                let normal = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    -theta.sin() * phi.sin(),
+                );
+                let uv = [sector as f32 / sectors as f32, ring as f32 / rings as f32];
+                self.vertices.push(
+                    Vertex::from_vec(center + normal * radius, Some(color), Some(uv))
+                        .with_normal(normal),
+                );
+            }
+        }
+
+        //Adding indices
+        self.add_grid_indices(index, sectors, rings);
+        self
+    }
+
+    ///Add two triangles for each cell of a grid of `(columns + 1) * (rows + 1)` vertices starting
+    ///at `index`. Triangles face the direction of the cross product between the next row and the
+    ///next column.
+    fn add_grid_indices(&mut self, index: u32, columns: u32, rows: u32) {
+        let stride = columns + 1;
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = index + row * stride + column;
+                let b = a + stride;
+                self.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+            }
+        }
+    }
+
+    ///Add a quad mapping a region of a texture onto it. Corners are given in bottom-left,
+    ///bottom-right, top-left, top-right order and `uv_rect` is the texture region in UV space.
+    pub fn add_textured_quad(&mut self, corners: [Vertex; 4], uv_rect: &Rect) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+
+        //Adding vertices
+        let min = uv_rect.pos;
+        let max = uv_rect.pos + uv_rect.size;
+        let uvs = [
+            [min.x, min.y],
+            [max.x, min.y],
+            [min.x, max.y],
+            [max.x, max.y],
+        ];
+        for (mut v, uv) in corners.into_iter().zip(uvs) {
+            v.uv = uv;
+            self.vertices.push(v);
+        }
+
+        //Adding indices
+        self.indices.push(index);
         self.indices.push(index + 1);
         self.indices.push(index + 2);
+        self.indices.push(index + 2);
+        self.indices.push(index + 1);
         self.indices.push(index + 3);
 
-        self.index_id_counter += 4;
         self
     }
 
-    ///Add a cube to the batch specifying the center, orientation, size and color.
+    ///Add a cube to the batch specifying the center, orientation, size and color. Faces are
+    ///counter clockwise when seen from outside.
     pub fn add_cube(
         &mut self, center: Vec3, orientation: Quat, scale: Vec3, color: Color,
     ) -> &mut ShapeBatch {
@@ -177,3 +936,156 @@ impl ShapeBatch {
         self
     }
 }
+
+/// Matrix transforming normals so they stay perpendicular to surfaces transformed by the matrix.
+fn normal_matrix(matrix: Mat4) -> Mat3 { Mat3::from_mat4(matrix).inverse().transpose() }
+
+fn offset_vertex(mut v: Vertex, offset: Vec2) -> Vertex {
+    v.pos[0] += offset.x;
+    v.pos[1] += offset.y;
+    v
+}
+
+fn sorted_angles(a: f32, b: f32) -> (f32, f32) {
+    if b < a {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn point_distance_sq(a: &Vertex, b: &Vertex) -> f32 {
+    Vec2::new(a.x() - b.x(), a.y() - b.y()).length_squared()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_after_triangle_indexes_its_own_vertices() {
+        let mut batch = ShapeBatch::new();
+        batch
+            .add_triangle([
+                vertex!(0.0, 0.0, 0.0),
+                vertex!(1.0, 0.0, 0.0),
+                vertex!(0.0, 1.0, 0.0),
+            ])
+            .add_square(
+                Vec3::new(5.0, 5.0, 0.0),
+                Vec3::Y,
+                Vec3::Z,
+                2.0,
+                2.0,
+                Color::RED,
+            );
+        assert_eq!(batch.vertices.len(), 7);
+        assert_eq!(batch.indices, [0, 1, 2, 3, 4, 5, 5, 4, 6]);
+        //Every square index points at a square corner, not at the triangle.
+        for i in &batch.indices[3..] {
+            let v = batch.vertices[*i as usize];
+            assert_eq!(v.color, Color::RED);
+            assert_eq!((v.x() - 5.0).abs(), 1.0);
+            assert_eq!((v.y() - 5.0).abs(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rounded_rect_vertex_count() {
+        let center = vertex!(0.0, 0.0, 0.0);
+        let mut batch = ShapeBatch::new();
+        batch.add_rounded_rect(center, 2.0, 1.0, 0.25, 4);
+        //Center plus each corner arc, including both of its ends.
+        assert_eq!(batch.vertices.len(), 1 + 4 * 5);
+        assert_eq!(batch.indices.len(), 4 * 5 * 3);
+
+        //No radius adds a plain square and negative sizes don't panic.
+        let mut batch = ShapeBatch::new();
+        batch.add_rounded_rect(center, 2.0, 1.0, 0.0, 4);
+        assert_eq!(batch.vertices.len(), 4);
+        let mut batch = ShapeBatch::new();
+        batch.add_rounded_rect(center, -2.0, 1.0, 0.25, 4);
+        assert_eq!(batch.vertices.len(), 1 + 4 * 5);
+    }
+
+    #[test]
+    fn fan_without_extent_has_finite_uvs() {
+        let mut batch = ShapeBatch::new();
+        batch
+            .add_regular_polygon(vertex!(0.0, 0.0, 0.0), 0.0, 5, 0.0)
+            .add_ellipse(vertex!(0.0, 0.0, 0.0), 1.0, 0.0, 8);
+        assert!(batch
+            .vertices
+            .iter()
+            .all(|v| v.uv.iter().all(|c| c.is_finite())));
+    }
+
+    #[test]
+    fn square_polygon_matches_square() {
+        let mut polygon = ShapeBatch::new();
+        polygon.add_regular_polygon(vertex!(1.0, 2.0, 0.0), 2f32.sqrt(), 4, 0.0);
+        let mut square = ShapeBatch::new();
+        square.add_2d_square(Vec3::new(1.0, 2.0, 0.0), 2.0, 2.0, Color::WHITE);
+        //Skip the fan center, every corner has to match a square corner.
+        for corner in &polygon.vertices[1..] {
+            let matching = square
+                .vertices
+                .iter()
+                .any(|v| v.position().distance(corner.position()) < 1e-5);
+            assert!(matching, "{:?} is not a square corner", corner);
+        }
+        assert_eq!(polygon.vertices.len() - 1, square.vertices.len());
+    }
+
+    #[test]
+    fn arc_without_outer_radius_is_skipped() {
+        let mut batch = ShapeBatch::new();
+        batch.add_arc(vertex!(0.0, 0.0, 0.0), 0.0, 0.0, 1.0, 8, 0.0);
+        batch.add_arc(vertex!(0.0, 0.0, 0.0), -1.0, 0.0, 1.0, 8, 0.5);
+        assert!(batch.vertices.is_empty() && batch.indices.is_empty());
+    }
+
+    #[test]
+    fn index_format_selection() {
+        let mut batch = ShapeBatch::new();
+        batch.add_2d_square(Vec3::ZERO, 1.0, 1.0, Color::WHITE);
+        assert_eq!(batch.index_format(), IndexFormat::Uint16);
+        assert_eq!(batch.index_data().1.len(), 6 * 2);
+
+        //Too many vertices for 16 bit indices.
+        let mut batch = ShapeBatch::new();
+        batch.vertices = vec![vertex!(0.0, 0.0, 0.0); u16::MAX as usize + 1];
+        batch.indices = vec![0, 1, u16::MAX as u32];
+        assert_eq!(batch.index_format(), IndexFormat::Uint32);
+        assert_eq!(batch.index_data().1.len(), 3 * 4);
+
+        //Few vertices but an index that doesn't fit.
+        let mut batch = ShapeBatch::new();
+        batch.vertices = vec![vertex!(0.0, 0.0, 0.0); 3];
+        batch.indices = vec![0, 1, u16::MAX as u32 + 1];
+        assert_eq!(batch.index_format(), IndexFormat::Uint32);
+    }
+
+    #[test]
+    fn add_mesh_offsets_indices() {
+        let mut batch = ShapeBatch::new();
+        batch.add_2d_square(Vec3::ZERO, 1.0, 1.0, Color::WHITE);
+        let vertices = [
+            vertex!(0.0, 0.0, 0.0),
+            vertex!(1.0, 0.0, 0.0),
+            vertex!(0.0, 1.0, 0.0),
+        ];
+        batch.add_mesh(&vertices, &[2, 1, 0]).unwrap();
+        assert_eq!(batch.vertices.len(), 7);
+        assert_eq!(&batch.indices[6..], [6, 5, 4]);
+        assert_eq!(batch.vertices[6].position(), Vec3::Y);
+    }
+
+    #[test]
+    fn add_mesh_rejects_out_of_bounds_indices() {
+        let mut batch = ShapeBatch::new();
+        let vertices = [vertex!(0.0, 0.0, 0.0), vertex!(1.0, 0.0, 0.0)];
+        assert!(batch.add_mesh(&vertices, &[0, 1, 2]).is_err());
+        assert!(batch.vertices.is_empty() && batch.indices.is_empty());
+    }
+}
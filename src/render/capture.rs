@@ -0,0 +1,200 @@
+use std::{borrow::Cow, path::PathBuf};
+
+use glam::UVec2;
+use wgpu::{
+    Buffer, CommandEncoder, Extent3d, FragmentState, ImageCopyTexture, ImageDataLayout,
+    MultisampleState, Operations, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipelineDescriptor, ShaderModuleDescriptor, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor, VertexState,
+};
+
+use crate::{map_read, padded_bytes_per_row, save_rgba8, unpad_rows, GpuCtx};
+
+/// Copy of the window frame requested with `GpuCtx::capture_frame`, saved once the frame has been
+/// submitted.
+pub(crate) struct FrameCapture {
+    path: PathBuf,
+    size: UVec2,
+    format: TextureFormat,
+    /// Texture drawn into instead of the surface when the surface can't be copied from.
+    intermediate: Option<wgpu::Texture>,
+    readback: Option<Buffer>,
+}
+impl FrameCapture {
+    /// Take the capture requested for the window, if any and if its surface format can be saved.
+    pub(crate) fn take_requested(gpu: &GpuCtx) -> Option<Self> {
+        let path = gpu.capture_path.borrow_mut().take()?;
+        let format = gpu.surface_config.format;
+        if !matches!(
+            format,
+            TextureFormat::Rgba8Unorm
+                | TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Bgra8Unorm
+                | TextureFormat::Bgra8UnormSrgb
+        ) {
+            log::warn!(
+                "Capturing frames with surface format {:?} is not supported.",
+                format
+            );
+            return None;
+        }
+        let size = UVec2::new(gpu.surface_config.width, gpu.surface_config.height);
+        let intermediate =
+            (!gpu.surface_config.usage.contains(TextureUsages::COPY_SRC)).then(|| {
+                gpu.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Frame capture texture"),
+                    size: Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT
+                        | TextureUsages::COPY_SRC
+                        | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+            });
+        Some(Self {
+            path,
+            size,
+            format,
+            intermediate,
+            readback: None,
+        })
+    }
+
+    /// View to draw the frame into instead of the surface, if the surface can't be copied from.
+    pub(crate) fn target_view(&self) -> Option<TextureView> {
+        self.intermediate
+            .as_ref()
+            .map(|texture| texture.create_view(&TextureViewDescriptor::default()))
+    }
+
+    /// Record copying the frame into a readback buffer. If the frame was drawn into the
+    /// intermediate texture it's also drawn into the surface texture, so it's presented as usual.
+    pub(crate) fn record(
+        &mut self, gpu: &GpuCtx, encoder: &mut CommandEncoder, frame: &wgpu::Texture,
+    ) {
+        let padded_bytes_per_row = padded_bytes_per_row(self.size.x, 4);
+        let readback = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame capture buffer"),
+            size: (padded_bytes_per_row * self.size.y) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: self.intermediate.as_ref().unwrap_or(frame),
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(self.size.y),
+                },
+            },
+            Extent3d {
+                width: self.size.x,
+                height: self.size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        if let Some(intermediate) = &self.intermediate {
+            self.blit(gpu, encoder, intermediate, frame);
+        }
+        self.readback = Some(readback);
+    }
+
+    /// Draw the source texture into the target one, which must have the same size.
+    fn blit(
+        &self, gpu: &GpuCtx, encoder: &mut CommandEncoder, source: &wgpu::Texture,
+        target: &wgpu::Texture,
+    ) {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Frame capture shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("blit.wgsl"))),
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Frame capture pipeline"),
+            layout: None,
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(self.format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        let source_view = source.create_view(&TextureViewDescriptor::default());
+        let target_view = target.create_view(&TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    /// Read back the recorded copy and save it as an image. Needs to be called after the commands
+    /// recorded have been submitted. Errors are logged since the frame can still be presented.
+    pub(crate) fn save(self, gpu: &GpuCtx) {
+        let readback = match self.readback {
+            Some(readback) => readback,
+            None => return,
+        };
+        let mut data = unpad_rows(
+            &map_read(gpu, &readback),
+            self.size.x as usize * 4,
+            padded_bytes_per_row(self.size.x, 4) as usize,
+        );
+        if matches!(
+            self.format,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        ) {
+            data.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+        }
+        if let Err(e) = save_rgba8(&self.path, self.size, data) {
+            log::error!("Error saving frame capture to {:?}: {}", self.path, e);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::{borrow::Cow, collections::HashMap, error::Error, fs::File, io::Read, path::Path};
+
+use wgpu::{
+    BindGroup, CommandEncoder, CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, PipelineLayoutDescriptor, ShaderModule, ShaderModuleDescriptor,
+};
+
+use crate::{catch_gpu_error, Binder, GpuCtx, LError, ToBinder};
+
+/// Used to run compute shaders on the GPU. Shader entry point must be `cs_main`.
+pub struct ComputeBrush {
+    compiled_shader: ShaderModule,
+    cached_pipeline: Option<ComputePipeline>,
+    cached_bindings: Vec<(u32, BindGroup)>,
+    assets_to_bind: HashMap<u32, Binder>,
+    needs_update: bool,
+}
+
+impl ComputeBrush {
+    /// Create compute brush from shader path.
+    pub fn from_path(gpu: &GpuCtx, shader_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut source = String::new();
+        File::open(shader_path)?.read_to_string(&mut source)?;
+        Self::from_source(gpu, source)
+    }
+
+    /// Create compute brush directly providing the shader source.
+    pub fn from_source(gpu: &GpuCtx, shader_source: String) -> Result<Self, Box<dyn Error>> {
+        let device = &gpu.device;
+        let shader = catch_gpu_error(device, || {
+            device.create_shader_module(ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(Cow::Owned(shader_source)),
+            })
+        })
+        .map_err(|e| {
+            let e = LError::from_gpu(e, LError::ShaderCompile);
+            log::error!("{}", e);
+            e
+        })?;
+        log::debug!("Compiled compute shader");
+        Ok(Self {
+            compiled_shader: shader,
+            cached_pipeline: None,
+            cached_bindings: Vec::new(),
+            assets_to_bind: HashMap::new(),
+            needs_update: true,
+        })
+    }
+
+    /// Bind asset given a group and location index. Both indices need to match with shader's.
+    /// Assets need to be visible in the compute stage.
+    pub fn bind(&mut self, group_index: u32, loc_index: u32, asset: impl ToBinder + 'static) {
+        self.assets_to_bind
+            .entry(group_index)
+            .or_insert_with(Binder::new)
+            .bind(loc_index, Box::new(asset));
+        self.needs_update = true;
+    }
+
+    /// Returns if brush has been modified and needs to update the GPU with new data.
+    pub fn needs_update(&self) -> bool { self.needs_update }
+
+    /// Update GPU bindings and pipeline with current brush data.
+    pub fn update(&mut self, gpu: &GpuCtx) -> Result<(), LError> {
+        let device = &gpu.device;
+        self.cached_bindings.clear();
+        let mut bgls: Vec<(u32, wgpu::BindGroupLayout)> = Vec::new();
+        for (i, binder) in self.assets_to_bind.iter() {
+            let (bgl, bg) = binder.bake(gpu);
+            bgls.push((*i, bgl));
+            self.cached_bindings.push((*i, bg));
+        }
+        bgls.sort_by_key(|x| x.0);
+        let pipeline = catch_gpu_error(device, || {
+            let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &bgls.iter().map(|x| &x.1).collect::<Vec<_>>(),
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                module: &self.compiled_shader,
+                entry_point: "cs_main",
+            })
+        })
+        .map_err(|e| {
+            let e = LError::from_gpu(e, LError::ShaderCompile);
+            log::error!("Error creating compute pipeline: {}", e);
+            e
+        })?;
+        self.cached_pipeline = Some(pipeline);
+        self.needs_update = false;
+        Ok(())
+    }
+}
+
+/// Records compute shader dispatches to be executed on the GPU.
+///
+/// #Examples
+/// ``` no_run
+/// use tridify_rs::*;
+/// fn fill(gpu: &GpuCtx, brush: &mut ComputeBrush) -> Result<(), LError> {
+///     let mut pass = gpu.start_compute_pass();
+///     pass.dispatch(gpu, brush, 64, 1, 1)?;
+///     pass.finish(gpu);
+///     Ok(())
+/// }
+/// ```
+pub struct ComputePass {
+    cmds: CommandEncoder,
+}
+impl ComputePass {
+    pub fn new(gpu: &GpuCtx) -> Self {
+        Self {
+            cmds: gpu
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+        }
+    }
+
+    /// Bind brush pipeline and groups and run `x * y * z` workgroups.
+    pub fn dispatch(
+        &mut self, gpu: &GpuCtx, brush: &mut ComputeBrush, x: u32, y: u32, z: u32,
+    ) -> Result<(), LError> {
+        if brush.needs_update() {
+            brush.update(gpu)?;
+        }
+        let mut pass = self
+            .cmds
+            .begin_compute_pass(&ComputePassDescriptor { label: None });
+        pass.set_pipeline(brush.cached_pipeline.as_ref().unwrap());
+        for (id, bg) in brush.cached_bindings.iter() {
+            pass.set_bind_group(*id, bg, &[]);
+        }
+        pass.dispatch_workgroups(x, y, z);
+        Ok(())
+    }
+
+    /// Submit all dispatches to the GPU without waiting for them, use `GpuCtx::wait_idle` or a
+    /// readback to wait for the results.
+    pub fn finish(self, gpu: &GpuCtx) { gpu.queue.submit(Some(self.cmds.finish())); }
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use wgpu::PrimitiveTopology;
+
+use crate::{Brush, BrushDesc, GpuCtx, ShapeBatch, Vertex};
+
+impl ShapeBatch {
+    ///Add a 1 pixel wide line between two vertices, to be drawn with `Brush::debug_lines` or any
+    ///brush using `PrimitiveTopology::LineList`. Lines are drawn by the GPU without expanding them
+    ///into quads, so batches using them can't contain triangles. Use `add_line` for wider lines.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch
+    ///     .add_line_segment(vertex!(0.0, 0.0, 0.0, Color::RED), vertex!(1.0, 0.0, 0.0, Color::RED))
+    ///     .add_line_segment(vertex!(0.0, 0.0, 0.0, Color::GREEN), vertex!(0.0, 1.0, 0.0, Color::GREEN));
+    /// assert_eq!(batch.indices, [0, 1, 2, 3]);
+    /// ```
+    pub fn add_line_segment(&mut self, a: Vertex, b: Vertex) -> &mut ShapeBatch {
+        let index = self.vertices.len() as u32;
+        self.vertices.push(a);
+        self.indices.push(index);
+        self.vertices.push(b);
+        self.indices.push(index + 1);
+        self
+    }
+}
+
+impl Brush {
+    /// Create brush drawing lines added with `ShapeBatch::add_line_segment` using their vertex
+    /// color. Positions are used as they are, in clip space. wgpu always draws lines 1 pixel wide,
+    /// so this is meant as a fast path for debug visualization.
+    pub fn debug_lines(gpu: &GpuCtx) -> Result<Brush, Box<dyn Error>> {
+        Brush::from_source(
+            BrushDesc {
+                topology: PrimitiveTopology::LineList,
+                cull_mode: None,
+                sample_count: gpu.sample_count(),
+                ..Default::default()
+            },
+            gpu,
+            include_str!("debug_lines.wgsl").to_string(),
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{Brush, DrawRange, GpuCtx, LError, RenderPass, ShapeBuffer};
+
+/// Draws collected from several brushes and issued together, grouped by brush so each pipeline and
+/// its bind groups are set only once.
+///
+/// Brushes are drawn in the order they were added with `add_brush`, and draws using the same brush
+/// keep the order they were added in. Shapes that need to be drawn in a specific order across
+/// brushes, such as overlapping transparent shapes, should use separate lists or passes.
+///
+/// #Examples
+/// ``` no_run
+/// use tridify_rs::*;
+/// fn draw<'a>(
+///     gpu: &GpuCtx, pass: &mut RenderPass<'a>, red: &'a mut Brush, blue: &'a mut Brush,
+///     shapes: &'a [ShapeBuffer],
+/// ) -> Result<(), LError> {
+///     let mut list = DrawList::new();
+///     let red = list.add_brush(red);
+///     let blue = list.add_brush(blue);
+///     //Alternating brushes only switches pipeline once when flushed.
+///     for (i, shape) in shapes.iter().enumerate() {
+///         list.add(if i % 2 == 0 { red } else { blue }, shape);
+///     }
+///     list.flush(gpu, pass)
+/// }
+/// ```
+#[derive(Default)]
+pub struct DrawList<'a> {
+    brushes: Vec<&'a mut Brush>,
+    draws: Vec<(usize, &'a ShapeBuffer, DrawRange)>,
+}
+impl<'a> DrawList<'a> {
+    pub fn new() -> Self {
+        Self {
+            brushes: Vec::new(),
+            draws: Vec::new(),
+        }
+    }
+
+    /// Add a brush to draw with, returning the ID used to add draws with it.
+    pub fn add_brush(&mut self, brush: &'a mut Brush) -> usize {
+        self.brushes.push(brush);
+        self.brushes.len() - 1
+    }
+
+    /// Draw the whole buffer with the brush added with the given ID.
+    pub fn add(&mut self, brush_id: usize, buffer: &'a ShapeBuffer) -> &mut DrawList<'a> {
+        self.add_range(brush_id, buffer, DrawRange::from(buffer))
+    }
+
+    /// Draw part of the buffer with the brush added with the given ID.
+    pub fn add_range(
+        &mut self, brush_id: usize, buffer: &'a ShapeBuffer, range: DrawRange,
+    ) -> &mut DrawList<'a> {
+        self.draws.push((brush_id, buffer, range));
+        self
+    }
+
+    /// Amount of draws added.
+    pub fn len(&self) -> usize { self.draws.len() }
+    pub fn is_empty(&self) -> bool { self.draws.is_empty() }
+
+    /// Update outdated brushes and issue all draws into the pass, setting the state of each brush
+    /// once. Fails if a draw uses a brush ID that wasn't added.
+    pub fn flush(self, gpu: &GpuCtx, pass: &mut RenderPass<'a>) -> Result<(), LError> {
+        let brush_count = self.brushes.len();
+        if let Some((brush_id, _, _)) = self.draws.iter().find(|(i, _, _)| *i >= brush_count) {
+            return Err(LError::Validation(format!(
+                "Draw uses brush {} but only {} brushes were added.",
+                brush_id, brush_count
+            )));
+        }
+        let mut brushes = Vec::with_capacity(brush_count);
+        for brush in self.brushes {
+            if brush.is_outdated(gpu) {
+                brush.update(gpu)?;
+            }
+            brushes.push(&*brush);
+        }
+        //Stable sort, so draws with the same brush keep their order.
+        let mut draws = self.draws;
+        draws.sort_by_key(|(brush_id, _, _)| *brush_id);
+        let mut current = None;
+        for (brush_id, buffer, range) in draws {
+            if current != Some(brush_id) {
+                pass.set_brush(brushes[brush_id]);
+                current = Some(brush_id);
+            }
+            pass.draw_buffer(buffer, range);
+        }
+        Ok(())
+    }
+}
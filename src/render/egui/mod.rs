@@ -6,7 +6,7 @@ use egui::FontDefinitions;
 use wgpu::Error;
 use winit::event::Event;
 
-use crate::GpuCtx;
+use crate::{GpuCtx, RenderPassBuilder};
 
 use self::egui_backend::*;
 
@@ -51,32 +51,51 @@ impl EguiPass {
         Self { egui_rp }
     }
 
+    /// Draw egui into a new frame and present it. Use `render_in` to draw on top of other render
+    /// passes instead.
     pub fn render(&mut self, gpu: &mut GpuCtx) {
-        let mut egui = gpu.egui.as_mut().unwrap();
         let output_frame = match gpu.surface.get_current_texture() {
             Ok(frame) => frame,
-            Err(wgpu::SurfaceError::Outdated) => {
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                 // This error occurs when the app is minimized on Windows.
                 // Silently return here to prevent spamming the console with:
                 // "The underlying surface has changed, and therefore the swap chain must be updated"
+                gpu.surface.configure(&gpu.device, &gpu.surface_config);
                 return;
             }
             Err(e) => {
-                eprintln!("Dropped frame with error: {}", e);
+                log::warn!("Dropped frame with error: {}", e);
                 return;
             }
         };
         let output_view = output_frame
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
-        let full_output = egui.platform.end_frame(Some(&gpu.winit_wnd));
-        let paint_jobs = egui.platform.context().tessellate(full_output.shapes);
 
         let mut encoder = gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("encoder"),
             });
+        self.draw(gpu, &mut encoder, &output_view, Some(wgpu::Color::BLACK));
+        // Submit the commands.
+        gpu.queue.submit(iter::once(encoder.finish()));
+        output_frame.present();
+    }
+
+    /// Draw egui on top of the frame being built, so it's presented together with the other
+    /// render passes when calling `RenderPassBuilder::finish_render`.
+    pub fn render_in(&mut self, gpu: &mut GpuCtx, builder: &mut RenderPassBuilder) {
+        self.draw(gpu, &mut builder.draw_cmds, &builder.frame_view, None);
+    }
+
+    fn draw(
+        &mut self, gpu: &mut GpuCtx, encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView, clear_color: Option<wgpu::Color>,
+    ) {
+        let egui = gpu.egui.as_mut().unwrap();
+        let full_output = egui.platform.end_frame(Some(&gpu.winit_wnd));
+        let paint_jobs = egui.platform.context().tessellate(full_output.shapes);
 
         let size = gpu.get_wnd_size();
         // Upload all resources for the GPU.
@@ -95,16 +114,13 @@ impl EguiPass {
         // Record all render passes.
         self.egui_rp
             .execute(
-                &mut encoder,
-                &output_view,
+                encoder,
+                output_view,
                 &paint_jobs,
                 &screen_descriptor,
-                Some(wgpu::Color::BLACK),
+                clear_color,
             )
             .unwrap();
-        // Submit the commands.
-        gpu.queue.submit(iter::once(encoder.finish()));
-        output_frame.present();
 
         self.egui_rp
             .remove_textures(tdelta)
@@ -0,0 +1,52 @@
+use std::{error::Error, fs::File, io::Read, path::Path};
+
+use crate::{vertex, Brush, BrushDesc, Color, GpuCtx, ShapeBatch, ShapeBuffer, ToBinder};
+
+/// Draws a single triangle covering the whole target, for post processing effects. Only the
+/// fragment stage needs to be provided, as `fn fs_main(in: FullscreenVertex) -> @location(0)
+/// vec4<f32>`, where `in.uv` goes from `(0, 0)` at the top left corner to `(1, 1)` at the bottom
+/// right one, matching texture coordinates.
+///
+/// To chain effects, draw each one into a texture with `GpuCtx::create_texture_render_builder` and
+/// bind that texture to the next pass.
+pub struct FullscreenPass {
+    brush: Brush,
+    triangle: ShapeBuffer,
+}
+
+impl FullscreenPass {
+    /// Create pass reading the fragment shader from the path.
+    pub fn from_path(
+        desc: BrushDesc, gpu: &GpuCtx, fragment_path: &Path,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut source = String::new();
+        File::open(fragment_path)?.read_to_string(&mut source)?;
+        Self::from_source(desc, gpu, &source)
+    }
+
+    /// Create pass directly providing the fragment shader source. The brush description is used
+    /// as is, so `targets` and `sample_count` need to match the target drawn into.
+    pub fn from_source(
+        desc: BrushDesc, gpu: &GpuCtx, fragment_source: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let source = format!("{}{}", include_str!("fullscreen.wgsl"), fragment_source);
+        let brush = Brush::from_source(desc, gpu, source)?;
+        let triangle = ShapeBatch::new()
+            .add_triangle([
+                vertex!(-1.0, -1.0, 0.0, Color::WHITE, [0.0, 1.0]),
+                vertex!(3.0, -1.0, 0.0, Color::WHITE, [2.0, 1.0]),
+                vertex!(-1.0, 3.0, 0.0, Color::WHITE, [0.0, -1.0]),
+            ])
+            .bake_buffers(gpu)?;
+        Ok(Self { brush, triangle })
+    }
+
+    /// Bind asset given a group and location index. See `Brush::bind`.
+    pub fn bind(&mut self, group_index: u32, loc_index: u32, asset: impl ToBinder + 'static) {
+        self.brush.bind(group_index, loc_index, asset);
+    }
+
+    pub(crate) fn parts(&mut self) -> (&mut Brush, &ShapeBuffer) {
+        (&mut self.brush, &self.triangle)
+    }
+}
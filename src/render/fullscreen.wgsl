@@ -0,0 +1,19 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct FullscreenVertex {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(model: VertexInput) -> FullscreenVertex {
+    var out: FullscreenVertex;
+    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.uv = model.uv;
+    return out;
+}
+
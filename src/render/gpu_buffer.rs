@@ -1,8 +1,12 @@
-use std::rc::Rc;
+use std::{
+    rc::Rc,
+    sync::mpsc::{Receiver, TryRecvError},
+};
 
-use wgpu::{util::DeviceExt, Buffer};
+use bytemuck::Pod;
+use wgpu::{util::DeviceExt, Buffer, BufferAsyncError};
 
-use crate::{GpuCtx, ToBinder, Window};
+use crate::{GpuCtx, LError, ResourceId, ToBinder, Window};
 
 pub trait ToGpuBuf {
     fn build_buffer(&self, wnd: &GpuCtx) -> GpuBuffer;
@@ -11,41 +15,127 @@ pub trait ToGpuBuf {
 /// Handle to a GPU buffer.
 pub struct GpuBuffer {
     buffer: Rc<Buffer>,
+    id: ResourceId,
 }
 
 impl GpuBuffer {
-    /// Creates a new buffer with uninitialized data.
-    fn new(wnd: &GpuCtx) -> Self {
+    /// Creates a new buffer with uninitialized data given its size in bytes.
+    pub fn new(wnd: &GpuCtx, size: u64, usage: wgpu::BufferUsages) -> Self {
         let buffer = wnd.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: todo!(),
-            usage: todo!(),
-            mapped_at_creation: todo!(),
+            size,
+            usage,
+            mapped_at_creation: false,
         });
         Self {
             buffer: Rc::new(buffer),
+            id: ResourceId::unique(),
         }
     }
 
-    /// Creates a buffer with the given bytes.
+    /// Creates a uniform buffer with the given bytes.
     pub fn init(wnd: &GpuCtx, data: &[u8]) -> Self {
+        Self::from_slice(
+            wnd,
+            data,
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        )
+    }
+
+    /// Creates a buffer containing all the elements of the slice.
+    pub fn from_slice<T: Pod>(wnd: &GpuCtx, data: &[T], usage: wgpu::BufferUsages) -> Self {
         let buffer = wnd
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: None,
-                contents: data,
-                //TODO: User config
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                contents: bytemuck::cast_slice(data),
+                usage,
             });
 
         Self {
             buffer: Rc::new(buffer),
+            id: ResourceId::unique(),
         }
     }
 
-    /// Update buffer GPU data with bytes provided.
-    pub fn write(&mut self, wnd: &GpuCtx, data: &[u8]) {
-        wnd.queue.write_buffer(&self.buffer, 0, data);
+    /// Creates a buffer containing a single value.
+    pub fn from_value<T: Pod>(wnd: &GpuCtx, value: T, usage: wgpu::BufferUsages) -> Self {
+        Self::from_slice(wnd, &[value], usage)
+    }
+
+    /// Update buffer GPU data with the elements provided, starting at `offset` bytes. Buffer needs
+    /// `COPY_DST` usage.
+    pub fn write<T: Pod>(&self, wnd: &GpuCtx, offset: u64, data: &[T]) {
+        wnd.queue
+            .write_buffer(&self.buffer, offset, bytemuck::cast_slice(data));
+    }
+
+    /// Size of the buffer in bytes.
+    pub fn size(&self) -> u64 { self.buffer.size() }
+
+    /// Start mapping the buffer to read it without blocking. The callback runs on the main thread
+    /// once the GPU has finished all the work submitted before this call, which is checked each
+    /// frame before rendering or manually with `GpuCtx::poll_buffer_maps`. Buffer needs
+    /// `MAP_READ` usage and can't be used by the GPU until the callback has run.
+    ///
+    /// For blocking reads in simple scripts use `StorageBuffer::read_back` instead.
+    ///
+    /// #Examples
+    /// ``` no_run
+    /// use tridify_rs::*;
+    /// fn read_picked_id(gpu: &GpuCtx, picking: &GpuBuffer) {
+    ///     //Called after submitting the pass that copies the picked id into `picking`.
+    ///     picking.map_async(gpu, |result| match result {
+    ///         Ok(data) => println!("Picked {}", bytemuck::pod_read_unaligned::<u32>(&data[..4])),
+    ///         Err(e) => eprintln!("{}", e),
+    ///     });
+    /// }
+    /// ```
+    pub fn map_async(&self, gpu: &GpuCtx, callback: impl FnOnce(Result<&[u8], LError>) + 'static) {
+        if !self.buffer.usage().contains(wgpu::BufferUsages::MAP_READ) {
+            callback(Err(LError::Validation(
+                "Buffer needs MAP_READ usage to be mapped.".into(),
+            )));
+            return;
+        }
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                sender.send(result).ok();
+            });
+        gpu.pending_maps.borrow_mut().push(PendingMap {
+            buffer: Rc::clone(&self.buffer),
+            receiver,
+            callback: Box::new(callback),
+        });
+    }
+}
+
+/// Buffer being mapped with the callback to run once it is.
+pub(crate) struct PendingMap {
+    buffer: Rc<Buffer>,
+    receiver: Receiver<Result<(), BufferAsyncError>>,
+    callback: Box<dyn FnOnce(Result<&[u8], LError>)>,
+}
+impl PendingMap {
+    /// Run the callback if the map finished, returning the pending map back otherwise.
+    pub(crate) fn try_finish(self) -> Option<Self> {
+        match self.receiver.try_recv() {
+            Ok(Ok(())) => {
+                let slice = self.buffer.slice(..);
+                let data = slice.get_mapped_range();
+                (self.callback)(Ok(&data));
+                drop(data);
+                self.buffer.unmap();
+            }
+            Ok(Err(e)) => (self.callback)(Err(LError::Validation(e.to_string()))),
+            Err(TryRecvError::Disconnected) => {
+                (self.callback)(Err(LError::Validation("Buffer map was cancelled.".into())))
+            }
+            Err(TryRecvError::Empty) => return Some(self),
+        }
+        None
     }
 }
 
@@ -72,6 +162,8 @@ impl ToBinder for GpuBuffer {
         }
     }
 
+    fn resource_id(&self) -> ResourceId { self.id }
+
     fn debug_name(&self) -> &'static str { "GPU Buffer" }
 }
 
@@ -79,6 +171,45 @@ impl Clone for GpuBuffer {
     fn clone(&self) -> Self {
         Self {
             buffer: Rc::clone(&self.buffer),
+            id: self.id,
         }
     }
 }
+
+/// Copy `size` bytes from the start of the buffer back to the CPU. Buffer needs `COPY_SRC` usage.
+/// Blocks until the GPU has finished the copy.
+pub(crate) fn read_buffer(gpu: &GpuCtx, buffer: &Buffer, size: u64) -> Vec<u8> {
+    //Copies need to be aligned, extra bytes are dropped after mapping.
+    let align = wgpu::COPY_BUFFER_ALIGNMENT;
+    let padded_size = (size + align - 1) / align * align;
+    let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback buffer"),
+        size: padded_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, padded_size);
+    gpu.queue.submit(Some(encoder.finish()));
+    map_read(gpu, &staging)[..size as usize].to_vec()
+}
+
+/// Map a `MAP_READ` buffer and copy its contents. Blocks until the GPU has finished all the work
+/// submitted, waiting with `Maintain::Wait` instead of polling so readbacks are deterministic.
+pub(crate) fn map_read(gpu: &GpuCtx, buffer: &Buffer) -> Vec<u8> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).ok();
+    });
+    gpu.device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("Buffer readback was cancelled.")
+        .expect("Error mapping readback buffer.");
+    let data = slice.get_mapped_range().to_vec();
+    buffer.unmap();
+    data
+}
@@ -1,40 +1,94 @@
 use std::{
+    cell::RefCell,
     collections::HashMap,
     error::Error,
     time::{Duration, Instant},
 };
 
-use glam::UVec2;
+use glam::{UVec2, Vec2};
 use wgpu::{
-    Adapter, Backends, Device, DeviceDescriptor, Features, InstanceDescriptor, Limits, Queue,
-    RequestAdapterOptions, Surface, SurfaceConfiguration, TextureFormat, TextureUsages,
+    Adapter, AdapterInfo, Backends, Device, DeviceDescriptor, Features, InstanceDescriptor, Limits,
+    PowerPreference, PresentMode, Queue, RequestAdapterOptions, Surface, SurfaceConfiguration,
+    TextureFormat, TextureUsages,
 };
 use winit::{
-    event::{Event, WindowEvent},
+    dpi::PhysicalSize,
+    event::{DeviceEvent, ElementState, Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
-    window::WindowId,
+    window::{WindowBuilder, WindowId},
 };
 
-use crate::{GpuCtx, RenderOptions, RenderPass, Texture, Window};
+use crate::{
+    create_depth_texture, create_msaa_texture, sample_count_supported, FrameStats, GpuCtx, Input,
+    RenderOptions, RenderPass, StatsOverlay, Texture, Window, WindowSettings, DEPTH_FORMAT,
+    DEPTH_STENCIL_FORMAT,
+};
 
 /// Represents basic information for a given windows rendering frame.
+#[derive(Clone)]
 pub struct FrameContext<'a> {
     //event loop
     // pub user_ctx: &'a T,
     pub delta_time: f64,
     pub elapsed_time: f64,
+    /// How far the render is between the last and the next fixed logic step, from 0 to 1. Always 1
+    /// when the window has no fixed timestep.
+    pub interpolation: f64,
+    /// Frames rendered by the window before this one.
+    pub frame_count: u64,
     pub winit_event: &'a Event<'a, ()>,
     eloop: &'a EventLoopWindowTarget<()>,
 }
 
+/// Configuration used to initialize WGPU and pick the adapter used by windows.
+pub struct TridifyOptions {
+    /// Graphic APIs allowed to be used.
+    pub backends: Backends,
+    /// Prefer integrated or discrete GPUs when both are available.
+    pub power_preference: PowerPreference,
+    /// Force using a software adapter.
+    pub force_fallback: bool,
+    /// Device features required by the application, such as `POLYGON_MODE_LINE`. Creating a window
+    /// fails if the adapter doesn't support them. `PUSH_CONSTANTS` is always enabled when
+    /// supported.
+    pub features: Features,
+    /// Device limits required by the application. Defaults to limits supported by WebGL2, which
+    /// don't allow storage buffers or compute shaders. Raise them, for example with
+    /// `Limits::downlevel_defaults()`, to use `StorageBuffer` or `ComputeBrush`.
+    pub limits: Limits,
+}
+impl Default for TridifyOptions {
+    fn default() -> Self {
+        Self {
+            backends: Backends::all(),
+            power_preference: PowerPreference::default(),
+            force_fallback: false,
+            features: Features::empty(),
+            limits: Limits::downlevel_webgl2_defaults(),
+        }
+    }
+}
+
 /// Root struct which initializes WGPU, starts window management and handles application loop.
 pub struct Tridify {
     windows: HashMap<WindowId, Window>,
     wb: Option<EventLoop<()>>,
     wgpu: wgpu::Instance,
+    options: TridifyOptions,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
 }
 impl Tridify {
-    pub fn new() -> Self {
+    pub fn new() -> Self { Self::with_options(TridifyOptions::default()) }
+
+    /// Initialize using the backends and adapter preferences provided.
+    pub fn with_options(options: TridifyOptions) -> Self {
+        Self::with_event_loop(options, EventLoop::new())
+    }
+
+    /// Initialize with an existing event loop. Only one can be created per process, so tests
+    /// build it themselves to be able to run off the main thread.
+    pub(crate) fn with_event_loop(options: TridifyOptions, event_loop: EventLoop<()>) -> Self {
         // cfg_if::cfg_if! {
         //     if #[cfg(target_arch = "wasm32")] {
         //         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -45,18 +99,55 @@ impl Tridify {
         // }
 
         Self {
-            wgpu: wgpu::Instance::new(InstanceDescriptor::default()),
-            wb: Some(EventLoop::new()),
+            wgpu: wgpu::Instance::new(InstanceDescriptor {
+                backends: options.backends,
+                ..Default::default()
+            }),
+            wb: Some(event_loop),
             windows: HashMap::new(),
+            options,
+            #[cfg(feature = "gamepad")]
+            gilrs: gilrs::Gilrs::new()
+                .map_err(|e| log::warn!("Gamepads are not available: {}", e))
+                .ok(),
         }
     }
 
+    /// Information about all the adapters available with the current backends, such as their name,
+    /// backend and device type.
+    pub fn list_adapters(&self) -> Vec<AdapterInfo> {
+        self.wgpu
+            .enumerate_adapters(self.options.backends)
+            .map(|adapter| adapter.get_info())
+            .collect()
+    }
+
     pub fn has_windows(&self) -> bool { !self.windows.is_empty() }
 
     pub fn destroy_window(&mut self, wnd_id: &WindowId) { self.windows.remove(wnd_id); }
 
+    /// Destroy window and exit application loop if there are no windows left.
+    fn close_window(&mut self, wnd_id: &WindowId, flow: &mut ControlFlow) {
+        self.destroy_window(wnd_id);
+        if !self.has_windows() {
+            *flow = ControlFlow::Exit;
+        }
+    }
+
+    /// Create window using default settings.
     pub fn create_window(&mut self) -> Result<&mut Window, Box<dyn Error>> {
-        let wnd = winit::window::Window::new(self.wb.as_ref().unwrap())?;
+        self.create_window_with(WindowSettings::default())
+    }
+
+    /// Create window using the settings provided.
+    pub fn create_window_with(
+        &mut self, settings: WindowSettings,
+    ) -> Result<&mut Window, Box<dyn Error>> {
+        let mut wnd_builder = WindowBuilder::new().with_title(&settings.title);
+        if let Some(size) = settings.size {
+            wnd_builder = wnd_builder.with_inner_size(PhysicalSize::new(size.x, size.y));
+        }
+        let wnd = wnd_builder.build(self.wb.as_ref().unwrap())?;
         let wnd_id = wnd.id();
         let surface = unsafe {
             self.wgpu
@@ -64,30 +155,102 @@ impl Tridify {
                 .expect("Error creating window surface")
         };
         let adapter = pollster::block_on(self.wgpu.request_adapter(&RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            force_fallback_adapter: false,
+            power_preference: self.options.power_preference,
+            force_fallback_adapter: self.options.force_fallback,
             compatible_surface: Some(&surface),
         }))
         .ok_or("Error requesting adapter.")?;
+        let info = adapter.get_info();
+        log::info!(
+            "Using adapter {} ({:?}, {:?})",
+            info.name,
+            info.backend,
+            info.device_type
+        );
 
+        let missing_features = self.options.features - adapter.features();
+        if !missing_features.is_empty() {
+            return Err(format!(
+                "Adapter doesn't support the requested features {:?}.",
+                missing_features
+            )
+            .into());
+        }
+        //Push constants are enabled whenever the adapter supports them, so brushes can use them.
+        let features = self.options.features | (adapter.features() & Features::PUSH_CONSTANTS);
+        let mut limits = self.options.limits.clone();
+        if features.contains(Features::PUSH_CONSTANTS) {
+            limits.max_push_constant_size = limits
+                .max_push_constant_size
+                .max(adapter.limits().max_push_constant_size);
+        }
         let (device, queue) = pollster::block_on(adapter.request_device(
             &DeviceDescriptor {
                 label: None,
-                features: Features::empty(),
-                limits: Limits::downlevel_webgl2_defaults(),
+                features,
+                limits,
             },
             None,
         ))?;
+        log::debug!("Created device with features {:?}", features);
+        let present_modes = surface.get_capabilities(&adapter).present_modes;
+        let requested_mode = settings.present_mode.unwrap_or(if settings.vsync {
+            PresentMode::Fifo
+        } else {
+            PresentMode::Mailbox
+        });
+        let present_mode = closest_present_mode(requested_mode, &present_modes);
+        if present_mode != requested_mode {
+            log::warn!(
+                "Present mode {:?} is not supported, using {:?} instead.",
+                requested_mode,
+                present_mode
+            );
+        }
         let surface_config = SurfaceConfiguration {
             view_formats: vec![surface.get_capabilities(&adapter).formats[0]],
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface.get_capabilities(&adapter).formats[0],
             width: wnd.inner_size().width,
             height: wnd.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode,
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
         };
+        let sample_count = settings.sample_count;
+        let depth_format = if settings.stencil {
+            DEPTH_STENCIL_FORMAT
+        } else {
+            DEPTH_FORMAT
+        };
+        for format in [surface_config.format, depth_format] {
+            if !sample_count_supported(&adapter, &device, format, sample_count) {
+                return Err(format!(
+                    "Sample count {} is not supported for format {:?}.",
+                    sample_count, format
+                )
+                .into());
+            }
+        }
+        log::debug!(
+            "Configuring surface {}x{} with format {:?} and present mode {:?}",
+            surface_config.width,
+            surface_config.height,
+            surface_config.format,
+            surface_config.present_mode
+        );
         surface.configure(&device, &surface_config);
+        let msaa_texture = create_msaa_texture(
+            &device,
+            UVec2::new(surface_config.width, surface_config.height),
+            surface_config.format,
+            sample_count,
+        );
+        let depth_texture = create_depth_texture(
+            &device,
+            UVec2::new(surface_config.width, surface_config.height),
+            depth_format,
+            sample_count,
+        );
 
         // #[cfg(target_arch = "wasm32")]
         // {
@@ -106,16 +269,32 @@ impl Tridify {
         //         .expect("Couldn't append canvas to document body.");
         // }
 
+        let show_stats = settings.show_stats;
         let window = Window {
+            settings,
             user_loop: None,
+            logic_loop: None,
+            logic_accumulator: 0.0,
+            stats: FrameStats::default(),
             ctx: GpuCtx {
                 created_time: Instant::now(),
                 last_draw_time: Instant::now(),
+                last_frame_start: Instant::now(),
+                close_requested: false,
+                debug_wireframe: false,
+                pending_maps: RefCell::new(Vec::new()),
+                capture_path: RefCell::new(None),
+                stats_overlay: RefCell::new(show_stats.then(StatsOverlay::default)),
                 winit_wnd: wnd,
+                input: Input::default(),
                 adapter,
                 device,
                 queue,
                 surface_config,
+                sample_count,
+                msaa_texture,
+                depth_format,
+                depth_texture,
                 surface,
 
                 #[cfg(feature = "egui")]
@@ -146,45 +325,139 @@ impl Tridify {
                 }
 
                 match wnd_event {
-                    WindowEvent::CloseRequested => {
-                        self.destroy_window(&window_id);
-                        if !self.has_windows() {
-                            *flow = ControlFlow::Exit;
+                    WindowEvent::CloseRequested => self.close_window(&window_id, flow),
+                    WindowEvent::KeyboardInput { input, .. } => {
+                        let mut exit_requested = false;
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx.input.process_keyboard(input);
+                            exit_requested = input.state == ElementState::Pressed
+                                && input.virtual_keycode.is_some()
+                                && input.virtual_keycode == wnd.settings.exit_key;
+                        }
+                        if exit_requested {
+                            self.close_window(&window_id, flow);
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx
+                                .input
+                                .process_cursor(Vec2::new(position.x as f32, position.y as f32));
+                        }
+                    }
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx.input.process_mouse_button(*button, *state);
+                        }
+                    }
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx.input.process_scroll(delta);
                         }
                     }
                     WindowEvent::Resized(size) => {
-                        let wnd = self.get_window_mut(&window_id).unwrap();
-                        wnd.ctx
-                            .set_wnd_gpu_size(UVec2::new(size.width, size.height))
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx
+                                .set_wnd_gpu_size(UVec2::new(size.width, size.height))
+                        }
+                    }
+                    //Moving to a monitor with a different scale factor changes the physical size.
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                        if let Ok(wnd) = self.get_window_mut(&window_id) {
+                            wnd.ctx.set_wnd_gpu_size(UVec2::new(
+                                new_inner_size.width,
+                                new_inner_size.height,
+                            ))
+                        }
                     }
                     _ => {}
                 }
             }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => {
+                //Device events don't belong to any window.
+                for wnd in self.windows.values_mut() {
+                    wnd.ctx
+                        .input
+                        .process_mouse_motion(Vec2::new(delta.0 as f32, delta.1 as f32));
+                }
+            }
             Event::MainEventsCleared => {
+                #[cfg(feature = "gamepad")]
+                self.poll_gamepads();
+
+                let close_requested: Vec<WindowId> = self
+                    .windows
+                    .iter()
+                    .filter(|(_, wnd)| wnd.ctx().close_requested())
+                    .map(|(id, _)| *id)
+                    .collect();
+                for id in close_requested.iter() {
+                    self.close_window(id, flow);
+                }
                 for (id, wnd) in self.windows.iter_mut() {
-                    //TODO: User configurable
-                    if wnd.ctx().last_draw_time.elapsed() >= Duration::from_millis(16.6 as u64) {
+                    wnd.ctx().poll_buffer_maps();
+                    let frame_time = wnd
+                        .settings
+                        .max_fps
+                        .map(|fps| Duration::from_secs_f64(1.0 / fps.max(1) as f64))
+                        .unwrap_or_default();
+                    if wnd.ctx().last_draw_time.elapsed() >= frame_time {
                         wnd.view_mut().redraw();
                         wnd.view_mut().last_draw_time = Instant::now();
                     }
                 }
             }
             Event::RedrawRequested(id) => {
-                let wnd = self.get_window_mut(&id).unwrap();
-                let frame_ctx = FrameContext {
-                    delta_time: wnd.ctx().last_draw_time.elapsed().as_secs_f64(),
-                    elapsed_time: wnd.ctx().time_running().as_secs_f64(),
-                    winit_event: &event,
-                    // user_ctx: &user_ctx,
-                    eloop,
-                };
-                wnd.render_step(&frame_ctx);
-                wnd.view_mut().last_draw_time = Instant::now();
+                let mut fatal_error = false;
+                if let Ok(wnd) = self.get_window_mut(&id) {
+                    //Nothing to draw while minimized, surface can't be acquired anyway.
+                    if wnd.ctx().get_wnd_size().min_element() == 0 {
+                        return;
+                    }
+                    //Measure time between frame starts, independently of the frame rate cap.
+                    let frame_start = Instant::now();
+                    let delta_time = frame_start - wnd.ctx().last_frame_start;
+                    wnd.view_mut().last_frame_start = frame_start;
+                    let frame_ctx = FrameContext {
+                        delta_time: delta_time.as_secs_f64(),
+                        interpolation: 1.0,
+                        frame_count: wnd.stats().frame_count,
+                        elapsed_time: wnd.ctx().time_running().as_secs_f64(),
+                        winit_event: &event,
+                        // user_ctx: &user_ctx,
+                        eloop,
+                    };
+                    let result = wnd.render_step(&frame_ctx);
+                    wnd.view_mut().last_draw_time = Instant::now();
+                    if let Err(e) = result {
+                        log::error!("Closing window due to fatal error: {}", e);
+                        fatal_error = true;
+                    }
+                }
+                if fatal_error {
+                    self.close_window(&id, flow);
+                }
             }
             _ => {}
         });
     }
 
+    /// Send pending gamepad events to every window, since they don't belong to any of them.
+    #[cfg(feature = "gamepad")]
+    fn poll_gamepads(&mut self) {
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            for wnd in self.windows.values_mut() {
+                wnd.ctx.input.process_gamepad(&event);
+            }
+        }
+    }
+
     pub fn get_window(&self, id: &WindowId) -> Result<&Window, &str> {
         self.windows.get(id).ok_or("No window found.")
     }
@@ -192,3 +465,20 @@ impl Tridify {
         self.windows.get_mut(id).ok_or("No window found.")
     }
 }
+
+/// Pick the requested present mode if available, otherwise the most similar one. `Fifo` is
+/// always supported so it's used as the last resort.
+fn closest_present_mode(requested: PresentMode, available: &[PresentMode]) -> PresentMode {
+    let fallbacks: &[PresentMode] = match requested {
+        PresentMode::Immediate => &[PresentMode::Mailbox],
+        PresentMode::Mailbox => &[PresentMode::Immediate],
+        PresentMode::FifoRelaxed => &[],
+        //Auto modes are resolved by wgpu.
+        _ => return requested,
+    };
+    std::iter::once(&requested)
+        .chain(fallbacks)
+        .find(|mode| available.contains(mode))
+        .copied()
+        .unwrap_or(PresentMode::Fifo)
+}
@@ -1,26 +1,68 @@
 mod binders;
 mod brush;
 mod buffers;
+mod capture;
+mod compute;
+mod debug_lines;
+mod draw_list;
 
 #[cfg(feature = "egui")]
 mod egui;
 
+mod fullscreen;
 mod gpu_buffer;
 mod graphics;
+
+#[cfg(feature = "obj")]
+mod obj;
+
+mod picking;
+mod recorder;
+mod reflection;
 mod render_pass;
 mod sampler;
+mod sdf;
+mod sprite;
+mod storage_buffer;
+#[cfg(test)]
+mod test_gpu;
+#[cfg(feature = "testing")]
+mod testing;
+mod text;
+
 mod texture;
+mod uniform_buffer;
 mod vertex;
 
 #[cfg(feature = "egui")]
 pub use self::egui::*;
 
+#[cfg(feature = "obj")]
+pub use obj::*;
+
+#[cfg(feature = "testing")]
+pub use testing::*;
+
+#[cfg(test)]
+pub(crate) use test_gpu::*;
+
 pub use binders::*;
 pub use brush::*;
 pub use buffers::*;
+pub use capture::*;
+pub use compute::*;
+pub use draw_list::*;
+pub use fullscreen::*;
 pub use gpu_buffer::*;
 pub use graphics::*;
+pub use picking::*;
+pub use recorder::*;
+pub use reflection::*;
 pub use render_pass::*;
 pub use sampler::*;
+pub use sprite::*;
+pub use storage_buffer::*;
+pub use text::*;
 pub use texture::*;
+pub use uniform_buffer::*;
 pub use vertex::*;
@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use glam::{Mat4, Vec3};
+
+use crate::{GpuCtx, LError, ShapeBatch, ShapeBuffer, Vertex};
+
+/// Load a Wavefront OBJ model from disk into a shape batch. All sub meshes in the file are merged
+/// into the same batch, keeping their positions, texture coordinates and normals. Meshes without
+/// normals get them generated with `ShapeBatch::recompute_normals`. Faces with more than 3 vertices
+/// are triangulated.
+pub fn load_obj_batch(path: &Path) -> Result<ShapeBatch, LError> {
+    let (models, _) = tobj::load_obj(
+        path,
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| LError::AssetLoad(format!("{}: {}", path.display(), e)))?;
+
+    let mut batch = ShapeBatch::new();
+    for model in models.iter() {
+        let mesh = &model.mesh;
+        let vertices: Vec<Vertex> = mesh
+            .positions
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(i, pos)| {
+                //OBJ texture coordinates start at the bottom left, textures at the top left.
+                let uv = mesh
+                    .texcoords
+                    .get(i * 2..i * 2 + 2)
+                    .map(|uv| [uv[0], 1.0 - uv[1]]);
+                let vertex = Vertex::new(pos[0], pos[1], pos[2], None, uv);
+                match mesh.normals.get(i * 3..i * 3 + 3) {
+                    Some(n) => vertex.with_normal(Vec3::new(n[0], n[1], n[2])),
+                    None => vertex,
+                }
+            })
+            .collect();
+        if mesh.normals.is_empty() {
+            let mut mesh_batch = ShapeBatch::new();
+            mesh_batch
+                .add_mesh(&vertices, &mesh.indices)?
+                .recompute_normals();
+            batch.add_batch_transformed(&mesh_batch, Mat4::IDENTITY);
+        } else {
+            batch.add_mesh(&vertices, &mesh.indices)?;
+        }
+    }
+    Ok(batch)
+}
+
+/// Load a Wavefront OBJ model from disk and bake it into a single shape buffer. See
+/// [`load_obj_batch`] for how the file is read.
+pub fn load_obj(gpu: &GpuCtx, path: &Path) -> Result<ShapeBuffer, LError> {
+    load_obj_batch(path)?.bake_buffers(gpu)
+}
@@ -0,0 +1,78 @@
+use glam::{UVec2, UVec3};
+use wgpu::TextureFormat;
+
+use crate::{GpuCtx, LError, RenderPassBuilder, Texture, TextureDesc, TextureSize, TextureUsage};
+
+/// Offscreen target storing the ID of the object drawn on each pixel, used to find which object
+/// is under the cursor.
+///
+/// Objects are drawn into it with a brush targeting `PickingTarget::FORMAT`, whose fragment
+/// shader returns the object ID as `@location(0) u32`. Passes should clear it to 0, which is
+/// returned for pixels without objects, so IDs should start at 1. Afterwards `read_pixel` copies
+/// back only the pixel requested.
+///
+/// #Examples
+/// ``` no_run
+/// use tridify_rs::*;
+/// fn pick(gpu: &GpuCtx, picking: &PickingTarget, brush: &mut Brush, shapes: &ShapeBuffer) {
+///     //`brush` uses `BrushDesc { targets: vec![PickingTarget::FORMAT], ..Default::default() }`.
+///     let mut builder = picking.create_render_builder(gpu).unwrap();
+///     let mut pass = builder.build_render_pass(RenderOptions {
+///         clear_color: Some(Color::new(0.0, 0.0, 0.0, 0.0)),
+///         ..Default::default()
+///     });
+///     pass.render_shapes(gpu, brush, shapes).unwrap();
+///     pass.finish();
+///     builder.finish_render(gpu).unwrap();
+///
+///     let cursor = gpu.input().mouse_position();
+///     let id = picking.read_pixel(gpu, cursor.x as u32, cursor.y as u32).unwrap();
+///     println!("Object {}", id);
+/// }
+/// ```
+pub struct PickingTarget {
+    texture: Texture,
+}
+impl PickingTarget {
+    /// Format of the picking texture, one unsigned integer ID per pixel.
+    pub const FORMAT: TextureFormat = TextureFormat::R32Uint;
+
+    /// Create a picking target with the given size in pixels, usually the window size.
+    pub fn new(gpu: &GpuCtx, size: UVec2) -> Self {
+        let texture = Texture::new(
+            gpu,
+            TextureDesc {
+                size: TextureSize::D2(size.max(UVec2::ONE)),
+                usage: TextureUsage::RENDER | TextureUsage::SOURCE,
+                format: Self::FORMAT,
+                mip_level_count: 1,
+            },
+            Some("Picking texture"),
+        );
+        Self { texture }
+    }
+
+    /// Size of the target in pixels.
+    pub fn size(&self) -> UVec2 { self.texture.desc.size.get_size().truncate() }
+
+    /// Texture the IDs are drawn into.
+    pub fn texture(&self) -> &Texture { &self.texture }
+
+    /// Create a render builder drawing into the picking texture.
+    pub fn create_render_builder(&self, gpu: &GpuCtx) -> Result<RenderPassBuilder, LError> {
+        RenderPassBuilder::from_texture(gpu, &self.texture)
+    }
+
+    /// Read back the ID drawn at the given pixel, with the origin at the top left corner. Returns 0
+    /// for pixels outside of the target. Blocks until the GPU has finished drawing.
+    pub fn read_pixel(&self, gpu: &GpuCtx, x: u32, y: u32) -> Result<u32, LError> {
+        let size = self.size();
+        if x >= size.x || y >= size.y {
+            return Ok(0);
+        }
+        let data = self
+            .texture
+            .read_region_pixels(gpu, UVec3::new(x, y, 0), UVec3::ONE)?;
+        Ok(bytemuck::pod_read_unaligned(&data[..4]))
+    }
+}
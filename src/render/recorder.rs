@@ -0,0 +1,51 @@
+use std::{error::Error, fs, path::Path};
+
+use crate::{GpuCtx, Texture, TextureUsage};
+
+/// Render an animation offscreen, calling `render` for each of the `frames` with the frame index
+/// and a fixed `delta` in seconds, then saving the target texture into `directory` as
+/// `frame_0001.png`, `frame_0002.png`, etc. Nothing is presented to the window, so the result
+/// doesn't depend on the window refresh rate.
+///
+/// The closure should draw into `target`, for example with `GpuCtx::create_texture_render_builder`.
+/// The target needs `TextureUsage::RENDER | TextureUsage::SOURCE` and a format supported by
+/// `Texture::save_to_path`. The directory is created if it doesn't exist.
+///
+/// #Examples
+/// ``` no_run
+/// use std::path::Path;
+/// use tridify_rs::*;
+/// fn record(gpu: &GpuCtx, target: &Texture, brush: &mut Brush, shapes: &ShapeBuffer) {
+///     //Two seconds at 30 frames per second.
+///     record_frames(gpu, target, 60, 1.0 / 30.0, Path::new("frames"), |gpu, target, frame, delta| {
+///         let mut builder = gpu.create_texture_render_builder(target)?;
+///         let mut pass = builder.build_render_pass(RenderOptions {
+///             clear_color: Some(Color::from_hsv(frame as f32 * delta as f32 * 180.0, 1.0, 1.0, 1.0)),
+///             ..Default::default()
+///         });
+///         pass.render_shapes(gpu, brush, shapes)?;
+///         pass.finish();
+///         builder.finish_render(gpu)?;
+///         Ok(())
+///     })
+///     .unwrap();
+/// }
+/// ```
+pub fn record_frames(
+    gpu: &GpuCtx, target: &Texture, frames: u32, delta: f64, directory: &Path,
+    mut render: impl FnMut(&GpuCtx, &Texture, u32, f64) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    if !target
+        .desc
+        .usage
+        .contains(TextureUsage::RENDER | TextureUsage::SOURCE)
+    {
+        return Err("Recording frames requires RENDER and SOURCE usages.".into());
+    }
+    fs::create_dir_all(directory)?;
+    for frame in 0..frames {
+        render(gpu, target, frame, delta)?;
+        target.save_to_path(gpu, &directory.join(format!("frame_{:04}.png", frame + 1)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::fmt::Display;
+
+use naga::{AddressSpace, ImageClass, StorageAccess, TypeInner};
+use wgpu::{BindGroupLayoutEntry, BindingType, BufferBindingType, TextureSampleType};
+
+use crate::LError;
+
+/// Kind of resource a shader binding expects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ResourceKind {
+    Uniform,
+    Storage { writable: bool },
+    Texture { depth: bool },
+    StorageTexture,
+    Sampler { comparison: bool },
+    Unknown,
+}
+impl ResourceKind {
+    fn from_layout(entry: &BindGroupLayoutEntry) -> Self {
+        match entry.ty {
+            BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                ..
+            } => ResourceKind::Uniform,
+            BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                ..
+            } => ResourceKind::Storage {
+                writable: !read_only,
+            },
+            BindingType::Texture { sample_type, .. } => ResourceKind::Texture {
+                depth: sample_type == TextureSampleType::Depth,
+            },
+            BindingType::StorageTexture { .. } => ResourceKind::StorageTexture,
+            BindingType::Sampler(ty) => ResourceKind::Sampler {
+                comparison: ty == wgpu::SamplerBindingType::Comparison,
+            },
+        }
+    }
+
+    /// If a resource of this kind can be bound where the shader expects `shader`.
+    fn fits(self, shader: ResourceKind) -> bool {
+        match (self, shader) {
+            //Writable buffers can be bound to read only shader variables.
+            (ResourceKind::Storage { writable }, ResourceKind::Storage { writable: needed }) => {
+                writable || !needed
+            }
+            (_, ResourceKind::Unknown) => true,
+            (bound, shader) => bound == shader,
+        }
+    }
+}
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ResourceKind::Uniform => "uniform buffer",
+            ResourceKind::Storage { writable: true } => "writable storage buffer",
+            ResourceKind::Storage { writable: false } => "read only storage buffer",
+            ResourceKind::Texture { depth: true } => "depth texture",
+            ResourceKind::Texture { depth: false } => "texture",
+            ResourceKind::StorageTexture => "storage texture",
+            ResourceKind::Sampler { comparison: true } => "comparison sampler",
+            ResourceKind::Sampler { comparison: false } => "sampler",
+            ResourceKind::Unknown => "resource",
+        })
+    }
+}
+
+/// Resources declared by a WGSL shader, used to check assets bound to brushes match it.
+///
+/// #Examples
+/// ``` rust
+/// use tridify_rs::*;
+/// let shader = "@group(0) @binding(0) var<uniform> color: vec4<f32>;";
+/// let bindings = ShaderBindings::from_wgsl(shader).unwrap();
+/// let texture = wgpu::BindGroupLayoutEntry {
+///     binding: 3,
+///     visibility: wgpu::ShaderStages::FRAGMENT,
+///     ty: wgpu::BindingType::Texture {
+///         sample_type: wgpu::TextureSampleType::Float { filterable: true },
+///         view_dimension: wgpu::TextureViewDimension::D2,
+///         multisampled: false,
+///     },
+///     count: None,
+/// };
+/// let errors = bindings.mismatches(0, &[texture]);
+/// assert_eq!(errors, ["Group 0 binding 3: texture is bound but the shader does not declare it."]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShaderBindings {
+    bindings: Vec<(u32, u32, ResourceKind)>,
+}
+impl ShaderBindings {
+    /// Read the resources declared in a WGSL shader source.
+    pub fn from_wgsl(source: &str) -> Result<Self, LError> {
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|e| LError::ShaderCompile(e.emit_to_string(source)))?;
+        let bindings = module
+            .global_variables
+            .iter()
+            .filter_map(|(_, var)| {
+                let binding = var.binding.as_ref()?;
+                let mut ty = &module.types[var.ty].inner;
+                if let TypeInner::BindingArray { base, .. } = ty {
+                    ty = &module.types[*base].inner;
+                }
+                let kind = match (var.space, ty) {
+                    (AddressSpace::Uniform, _) => ResourceKind::Uniform,
+                    (AddressSpace::Storage { access }, _) => ResourceKind::Storage {
+                        writable: access.contains(StorageAccess::STORE),
+                    },
+                    (_, TypeInner::Image { class, .. }) => match class {
+                        ImageClass::Sampled { .. } => ResourceKind::Texture { depth: false },
+                        ImageClass::Depth { .. } => ResourceKind::Texture { depth: true },
+                        ImageClass::Storage { .. } => ResourceKind::StorageTexture,
+                    },
+                    (_, TypeInner::Sampler { comparison }) => ResourceKind::Sampler {
+                        comparison: *comparison,
+                    },
+                    _ => ResourceKind::Unknown,
+                };
+                Some((binding.group, binding.binding, kind))
+            })
+            .collect();
+        Ok(Self { bindings })
+    }
+
+    /// Describe every binding of the group that is not declared by the shader or whose type does
+    /// not match the one declared. Returns an empty list if all of them match.
+    pub fn mismatches(&self, group: u32, entries: &[BindGroupLayoutEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let bound = ResourceKind::from_layout(entry);
+                let declared = self
+                    .bindings
+                    .iter()
+                    .find(|(g, b, _)| *g == group && *b == entry.binding)
+                    .map(|(_, _, kind)| *kind);
+                match declared {
+                    None => Some(format!(
+                        "Group {} binding {}: {} is bound but the shader does not declare it.",
+                        group, entry.binding, bound
+                    )),
+                    Some(declared) if !bound.fits(declared) => Some(format!(
+                        "Group {} binding {}: shader expects a {} but a {} is bound.",
+                        group, entry.binding, declared, bound
+                    )),
+                    Some(_) => None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{ShaderStages, TextureViewDimension};
+
+    use super::*;
+
+    const SHADER: &str = "
+        @group(0) @binding(0) var t_diffuse: texture_2d<f32>;
+        @group(0) @binding(1) var s_diffuse: sampler;
+    ";
+
+    fn texture_entry(binding: u32) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    #[test]
+    fn texture_in_nonexistent_slot_is_reported() {
+        let bindings = ShaderBindings::from_wgsl(SHADER).unwrap();
+        assert!(bindings.mismatches(0, &[texture_entry(0)]).is_empty());
+        assert_eq!(
+            bindings.mismatches(0, &[texture_entry(0), texture_entry(5)]),
+            ["Group 0 binding 5: texture is bound but the shader does not declare it."]
+        );
+        assert_eq!(
+            bindings.mismatches(2, &[texture_entry(0)]),
+            ["Group 2 binding 0: texture is bound but the shader does not declare it."]
+        );
+    }
+
+    #[test]
+    fn texture_in_sampler_slot_is_reported() {
+        let bindings = ShaderBindings::from_wgsl(SHADER).unwrap();
+        assert_eq!(
+            bindings.mismatches(0, &[texture_entry(1)]),
+            ["Group 0 binding 1: shader expects a sampler but a texture is bound."]
+        );
+    }
+}
@@ -1,40 +1,131 @@
-use std::error::Error;
+use std::{ops::Range, time::Duration};
 
 use wgpu::{
-    CommandEncoder, CommandEncoderDescriptor, Operations, RenderPassColorAttachment,
-    RenderPassDescriptor, SurfaceTexture, TextureView, TextureViewDescriptor,
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor, Features,
+    Operations, QuerySet, QuerySetDescriptor, QueryType, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, ShaderStages, SurfaceError,
+    SurfaceTexture, TextureFormat, TextureView, TextureViewDescriptor,
 };
 
+use glam::{UVec2, Vec2};
+
 use crate::core::Color;
 use crate::GpuCtx;
 use crate::Rect;
 use crate::ShapeBuffer;
+use crate::{
+    catch_gpu_error, create_depth_texture, create_msaa_texture, read_buffer, FrameCapture, LError,
+};
+use crate::{FullscreenPass, Texture, TextureSize, TextureUsage};
 
 use super::Brush;
 
+/// Format used for depth buffers.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/// Format used for depth buffers of windows created with `WindowSettings::stencil`, with 8 bits
+/// of stencil per pixel.
+pub const DEPTH_STENCIL_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
 /// Rendering configuration on how to create and represent the given frame.
 pub struct RenderOptions {
-    pub clear_color: Color,
+    /// Color used to clear the frame at the start of the pass. Use `None` to keep the existing
+    /// contents, for example to draw on top of a previous pass. Surface contents are undefined
+    /// on the first pass of a frame, so it should always clear. To accumulate frames, for example
+    /// for motion trails, draw without clearing into a texture that is kept between frames and then
+    /// draw that texture into the window.
+    pub clear_color: Option<Color>,
+    /// Attach the window depth buffer, cleared at the start of the pass. Brushes used in the pass
+    /// must enable depth or stencil in their `BrushDesc` and the other way around. Windows created
+    /// with `WindowSettings::stencil` also clear their stencil values to 0.
+    pub depth: bool,
 }
 
 impl Default for RenderOptions {
     fn default() -> Self {
         Self {
-            clear_color: Color::BLACK,
+            clear_color: Some(Color::BLACK),
+            depth: false,
+        }
+    }
+}
+
+/// Part of a shape buffer to draw.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawRange {
+    /// Range of indices to draw from the index buffer.
+    pub indices: Range<u32>,
+    /// Range of instances to draw. Use `0..1` for buffers without instances.
+    pub instances: Range<u32>,
+}
+impl From<&ShapeBuffer> for DrawRange {
+    fn from(buffer: &ShapeBuffer) -> Self {
+        Self {
+            indices: 0..buffer.index_len,
+            instances: 0..buffer.instance_len,
         }
     }
 }
 
+/// Timestamp queries written at the start and end of each pass.
+struct PassTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    max_passes: u32,
+    timed_passes: u32,
+    /// Query index to write once the current pass ends.
+    pending_end: Option<u32>,
+}
+
 pub struct RenderPassBuilder {
-    draw_cmds: CommandEncoder,
-    frame_view: TextureView,
-    frame_texture: SurfaceTexture,
+    pub(crate) draw_cmds: CommandEncoder,
+    pub(crate) frame_view: TextureView,
+    /// Color targets after the first one, when drawing into multiple textures.
+    extra_views: Vec<TextureView>,
+    msaa_view: Option<TextureView>,
+    depth_view: TextureView,
+    /// Depth buffer has a stencil aspect to clear.
+    stencil: bool,
+    frame_texture: Option<SurfaceTexture>,
+    pub(crate) target_size: UVec2,
+    timer: Option<PassTimer>,
+    capture: Option<FrameCapture>,
 }
 impl RenderPassBuilder {
-    pub fn new(wnd: &GpuCtx) -> Result<Self, Box<dyn Error>> {
-        let frame_texture = wnd.surface.get_current_texture()?;
-        let frame_view = frame_texture
-            .texture
+    /// Acquire the next window frame. If the surface was lost or is outdated it gets reconfigured
+    /// and `LError::SurfaceLost` is returned, so the frame can be skipped. Timeouts are retried
+    /// once before failing.
+    pub fn new(wnd: &GpuCtx) -> Result<Self, LError> {
+        let frame_texture = match wnd.surface.get_current_texture() {
+            Err(SurfaceError::Timeout) => wnd.surface.get_current_texture(),
+            result => result,
+        };
+        let frame_texture = match frame_texture {
+            Ok(frame_texture) => frame_texture,
+            Err(error @ (SurfaceError::Lost | SurfaceError::Outdated)) => {
+                log::warn!("Surface error {:?}, reconfiguring surface.", error);
+                wnd.surface.configure(&wnd.device, &wnd.surface_config);
+                return Err(error.into());
+            }
+            Err(error) => {
+                log::error!("Error acquiring frame: {:?}", error);
+                return Err(error.into());
+            }
+        };
+        let capture = FrameCapture::take_requested(wnd);
+        let frame_view = capture
+            .as_ref()
+            .and_then(FrameCapture::target_view)
+            .unwrap_or_else(|| {
+                frame_texture
+                    .texture
+                    .create_view(&TextureViewDescriptor::default())
+            });
+        let msaa_view = wnd
+            .msaa_texture
+            .as_ref()
+            .map(|texture| texture.create_view(&TextureViewDescriptor::default()));
+        let depth_view = wnd
+            .depth_texture
             .create_view(&TextureViewDescriptor::default());
         let mut draw_cmds = wnd
             .device
@@ -43,70 +134,426 @@ impl RenderPassBuilder {
         Ok(Self {
             draw_cmds,
             frame_view,
-            frame_texture,
+            extra_views: Vec::new(),
+            msaa_view,
+            depth_view,
+            stencil: wnd.depth_format == DEPTH_STENCIL_FORMAT,
+            frame_texture: Some(frame_texture),
+            target_size: UVec2::new(wnd.surface_config.width, wnd.surface_config.height),
+            timer: None,
+            capture,
         })
     }
 
+    /// Draw into a texture instead of the window, so it can be bound to a brush afterwards. The
+    /// texture needs a 2D size and `TextureUsage::RENDER`, plus `TextureUsage::TEXTURE_BIND` to be
+    /// sampled later. Only the first mip level is drawn into.
+    ///
+    /// Brushes used must set `BrushDesc::targets` to the texture format and a `sample_count` of 1.
+    /// A depth buffer matching the texture size is created for the passes.
+    pub fn from_texture(gpu: &GpuCtx, target: &Texture) -> Result<Self, LError> {
+        Self::from_textures(gpu, &[target])
+    }
+
+    /// Draw into a texture with multisampling, like `from_texture` but drawing into a temporary
+    /// multisampled texture that is resolved into the target at the end of each pass. Brushes
+    /// used must set `BrushDesc::sample_count` to the same sample count, which needs to be
+    /// supported by the target format, see `GpuCtx::supports_sample_count`. A sample count of 1
+    /// is the same as `from_texture`.
+    ///
+    /// The multisampled texture doesn't contain the previous target contents, so the first pass
+    /// should clear it instead of loading.
+    pub fn from_texture_multisampled(
+        gpu: &GpuCtx, target: &Texture, sample_count: u32,
+    ) -> Result<Self, LError> {
+        let mut builder = Self::from_texture(gpu, target)?;
+        if sample_count <= 1 {
+            return Ok(builder);
+        }
+        for format in [target.desc.format, gpu.depth_format] {
+            if !gpu.supports_sample_count(format, sample_count) {
+                return Err(LError::Validation(format!(
+                    "Sample count {} is not supported for format {:?}.",
+                    sample_count, format
+                )));
+            }
+        }
+        builder.msaa_view = create_msaa_texture(
+            &gpu.device,
+            builder.target_size,
+            target.desc.format,
+            sample_count,
+        )
+        .map(|texture| texture.create_view(&TextureViewDescriptor::default()));
+        builder.depth_view = create_depth_texture(
+            &gpu.device,
+            builder.target_size,
+            gpu.depth_format,
+            sample_count,
+        )
+        .create_view(&TextureViewDescriptor::default());
+        Ok(builder)
+    }
+
+    /// Draw into multiple textures at once, for example to fill a G-buffer. Each texture is
+    /// written by the shader at the `@location` matching its index. All textures need the same
+    /// size and the requirements of `from_texture`, and brushes used must set `BrushDesc::targets`
+    /// to their formats in the same order.
+    pub fn from_textures(gpu: &GpuCtx, targets: &[&Texture]) -> Result<Self, LError> {
+        let first = targets.first().ok_or_else(|| {
+            LError::Validation("At least one render target texture is needed.".to_string())
+        })?;
+        let size = match first.desc.size {
+            TextureSize::D2(size) => size,
+            _ => {
+                return Err(LError::Validation(
+                    "Render target texture needs a 2D size.".to_string(),
+                ))
+            }
+        };
+        for target in targets {
+            if !matches!(target.desc.size, TextureSize::D2(target_size) if target_size == size) {
+                return Err(LError::Validation(
+                    "Render target textures need the same 2D size.".to_string(),
+                ));
+            }
+            if !target.desc.usage.contains(TextureUsage::RENDER) {
+                return Err(LError::Validation(
+                    "Render target texture needs TextureUsage::RENDER.".to_string(),
+                ));
+            }
+        }
+        let mut views = targets.iter().map(|target| {
+            target.texture.create_view(&TextureViewDescriptor {
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        });
+        let frame_view = views.next().unwrap();
+        let extra_views = views.collect();
+        let depth_view =
+            create_depth_texture(&gpu.device, UVec2::new(size.x, size.y), gpu.depth_format, 1)
+                .create_view(&TextureViewDescriptor::default());
+        let draw_cmds = gpu
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        Ok(Self {
+            draw_cmds,
+            frame_view,
+            extra_views,
+            msaa_view: None,
+            depth_view,
+            stencil: gpu.depth_format == DEPTH_STENCIL_FORMAT,
+            frame_texture: None,
+            target_size: size,
+            timer: None,
+            capture: None,
+        })
+    }
+
+    /// Measure how long the GPU takes to execute each pass built afterwards, up to `max_passes`.
+    /// Timings are returned by `finish_render_timed`. Requires the `TIMESTAMP_QUERY` device
+    /// feature.
+    pub fn with_timestamps(mut self, gpu: &GpuCtx, max_passes: u32) -> Result<Self, LError> {
+        if !gpu.device.features().contains(Features::TIMESTAMP_QUERY) {
+            return Err(LError::Validation(
+                "Timestamps require device feature TIMESTAMP_QUERY which is not enabled."
+                    .to_string(),
+            ));
+        }
+        let query_count = max_passes * 2;
+        self.timer = Some(PassTimer {
+            query_set: gpu.device.create_query_set(&QuerySetDescriptor {
+                label: Some("Pass timestamps"),
+                ty: QueryType::Timestamp,
+                count: query_count,
+            }),
+            resolve_buffer: gpu.device.create_buffer(&BufferDescriptor {
+                label: Some("Pass timestamps"),
+                size: query_count as u64 * std::mem::size_of::<u64>() as u64,
+                usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            max_passes,
+            timed_passes: 0,
+            pending_end: None,
+        });
+        Ok(self)
+    }
+
+    /// Write the end timestamp of the last timed pass, which has finished once the builder can be
+    /// used again.
+    fn end_timed_pass(&mut self) {
+        if let Some(timer) = &mut self.timer {
+            if let Some(query) = timer.pending_end.take() {
+                self.draw_cmds.write_timestamp(&timer.query_set, query);
+            }
+        }
+    }
+
+    /// Start a pass drawing into the builder targets. Clearing works the same for the window and
+    /// for textures: `RenderOptions::clear_color` clears every color target at the start of the
+    /// pass, and `RenderOptions::depth` attaches and clears the depth buffer. There is no other
+    /// way to clear a target, passes with `clear_color: None` keep the existing contents.
     pub fn build_render_pass(&mut self, options: RenderOptions) -> RenderPass {
+        self.end_timed_pass();
+        if let Some(timer) = &mut self.timer {
+            if timer.timed_passes < timer.max_passes {
+                let query = timer.timed_passes * 2;
+                self.draw_cmds.write_timestamp(&timer.query_set, query);
+                timer.pending_end = Some(query + 1);
+                timer.timed_passes += 1;
+            }
+        }
+        //When multisampling, draw into the multisampled texture and resolve into the frame.
+        let (view, resolve_target) = match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(&self.frame_view)),
+            None => (&self.frame_view, None),
+        };
+        let ops = Operations {
+            load: match options.clear_color {
+                Some(color) => wgpu::LoadOp::Clear(color.into()),
+                None => wgpu::LoadOp::Load,
+            },
+            store: true,
+        };
+        let mut color_attachments = vec![Some(RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops,
+        })];
+        color_attachments.extend(self.extra_views.iter().map(|view| {
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops,
+            })
+        }));
         let pass = self.draw_cmds.begin_render_pass(&RenderPassDescriptor {
             label: None,
-            color_attachments: &[Some(RenderPassColorAttachment {
-                view: &self.frame_view,
-                resolve_target: None,
-                ops: Operations {
-                    load: wgpu::LoadOp::Clear(options.clear_color.into()),
+            color_attachments: &color_attachments,
+            depth_stencil_attachment: options.depth.then(|| RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: self.stencil.then_some(Operations {
+                    load: wgpu::LoadOp::Clear(0),
                     store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
+                }),
+            }),
         });
-        RenderPass { pass }
+        RenderPass {
+            pass,
+            target_size: self.target_size,
+        }
     }
 
-    pub fn finish_render(self, wnd: &GpuCtx) {
-        wnd.queue.submit(Some(self.draw_cmds.finish()));
-        self.frame_texture.present();
+    /// Submit all passes. When drawing into the window the frame stats are drawn on top if
+    /// enabled, and the frame is presented after saving it if a capture was requested with
+    /// `GpuCtx::capture_frame`. Doesn't wait for the GPU to finish,
+    /// reading back the target or `GpuCtx::wait_idle` do.
+    pub fn finish_render(mut self, wnd: &GpuCtx) -> Result<(), LError> {
+        if self.frame_texture.is_some() {
+            if let Some(overlay) = wnd.stats_overlay.borrow_mut().as_mut() {
+                overlay.draw(wnd, &mut self)?;
+            }
+        }
+        let mut draw_cmds = self.draw_cmds;
+        let mut capture = self.capture;
+        if let (Some(capture), Some(frame_texture)) = (&mut capture, &self.frame_texture) {
+            capture.record(wnd, &mut draw_cmds, &frame_texture.texture);
+        }
+        catch_gpu_error(&wnd.device, || wnd.queue.submit(Some(draw_cmds.finish())))
+            .map_err(|e| LError::from_gpu(e, LError::Validation))?;
+        if let Some(capture) = capture {
+            capture.save(wnd);
+        }
+        if let Some(frame_texture) = self.frame_texture {
+            frame_texture.present();
+        }
+        Ok(())
+    }
+
+    /// Submit all passes like `finish_render`, then wait for the GPU and return how long each
+    /// timed pass took, in the order they were built. Timestamps are converted from GPU ticks
+    /// using the queue timestamp period, the nanoseconds each tick lasts.
+    pub fn finish_render_timed(mut self, wnd: &GpuCtx) -> Result<Vec<Duration>, LError> {
+        self.end_timed_pass();
+        let timer = self.timer.take().ok_or_else(|| {
+            LError::Validation("Timestamps were not enabled with `with_timestamps`.".to_string())
+        })?;
+        let query_count = timer.timed_passes * 2;
+        if query_count > 0 {
+            self.draw_cmds.resolve_query_set(
+                &timer.query_set,
+                0..query_count,
+                &timer.resolve_buffer,
+                0,
+            );
+        }
+        self.finish_render(wnd)?;
+        if query_count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let data = read_buffer(
+            wnd,
+            &timer.resolve_buffer,
+            query_count as u64 * std::mem::size_of::<u64>() as u64,
+        );
+        let period = wnd.queue.get_timestamp_period() as f64;
+        Ok(data
+            .chunks_exact(2 * std::mem::size_of::<u64>())
+            .map(|pass| {
+                let start: u64 = bytemuck::pod_read_unaligned(&pass[..8]);
+                let end: u64 = bytemuck::pod_read_unaligned(&pass[8..]);
+                Duration::from_nanos((end.saturating_sub(start) as f64 * period) as u64)
+            })
+            .collect())
     }
 }
 
 /// Manages the current frame being drawn.
 pub struct RenderPass<'a> {
     pass: wgpu::RenderPass<'a>,
+    target_size: UVec2,
 }
 
 impl<'a> RenderPass<'a> {
-    /// Rect provided needs to be in pixels.
+    /// Only draw pixels inside the rect on the following draws. Rect provided needs to be in
+    /// pixels, and is clamped to the render target.
     pub fn set_scissor(&mut self, rect: &Rect) {
-        self.pass.set_scissor_rect(
-            rect.pos.x as u32,
-            rect.pos.y as u32,
-            rect.size.x as u32,
-            rect.size.y as u32,
+        let (pos, size) = self.clamp_to_target(rect);
+        self.pass.set_scissor_rect(pos.x, pos.y, size.x, size.y);
+    }
+
+    /// Map the clip space of the following draws into the rect, for example to draw split screen
+    /// views. Rect provided needs to be in pixels, and is clamped to the render target. Depth range
+    /// needs to be within `0.0..=1.0`.
+    pub fn set_viewport(
+        &mut self, rect: &Rect, min_depth: f32, max_depth: f32,
+    ) -> Result<(), LError> {
+        if !(0.0..=1.0).contains(&min_depth) || !(min_depth..=1.0).contains(&max_depth) {
+            return Err(LError::Validation(format!(
+                "Invalid viewport depth range {}..{}.",
+                min_depth, max_depth
+            )));
+        }
+        let (pos, size) = self.clamp_to_target(rect);
+        if size.x == 0 || size.y == 0 {
+            return Err(LError::Validation(
+                "Viewport is outside the render target.".to_string(),
+            ));
+        }
+        self.pass.set_viewport(
+            pos.x as f32,
+            pos.y as f32,
+            size.x as f32,
+            size.y as f32,
+            min_depth,
+            max_depth,
         );
+        Ok(())
+    }
+
+    /// Set the reference value brushes compare stencil values against and write with
+    /// `StencilOperation::Replace`. It's 0 at the start of each pass.
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.pass.set_stencil_reference(reference);
+    }
+
+    /// Set push constants used by the following draws, starting at `offset` bytes. Brushes drawn
+    /// need `BrushDesc::push_constant_size` large enough to hold them and `stages` needs to be
+    /// `PUSH_CONSTANT_STAGES`, the stages brushes declare them for. Offset and data size need to
+    /// be multiples of 4.
+    pub fn set_push_constants(&mut self, stages: ShaderStages, offset: u32, data: &[u8]) {
+        self.pass.set_push_constants(stages, offset, data);
+    }
+
+    /// Position and size of the rect in pixels, limited to the render target.
+    fn clamp_to_target(&self, rect: &Rect) -> (UVec2, UVec2) {
+        let target = self.target_size.as_vec2();
+        let min = rect.pos.clamp(Vec2::ZERO, target);
+        let max = (rect.pos + rect.size).clamp(min, target);
+        (min.as_uvec2(), (max - min).as_uvec2())
     }
 
     ///Draw batch on the canvas.
-    pub fn render_shapes(&mut self, wnd: &GpuCtx, brush: &'a mut Brush, buffer: &'a ShapeBuffer) {
-        if brush.needs_update() {
-            brush.update(wnd);
+    pub fn render_shapes(
+        &mut self, wnd: &GpuCtx, brush: &'a mut Brush, buffer: &'a ShapeBuffer,
+    ) -> Result<(), LError> {
+        if brush.is_outdated(wnd) {
+            brush.update(wnd)?;
         }
         self.render_shapes_cached(brush, buffer);
+        Ok(())
+    }
+
+    /// Draw the fullscreen pass covering the whole target.
+    pub fn render_fullscreen(
+        &mut self, wnd: &GpuCtx, pass: &'a mut FullscreenPass,
+    ) -> Result<(), LError> {
+        let (brush, triangle) = pass.parts();
+        self.render_shapes(wnd, brush, triangle)
     }
 
     /// Draw batch on canvas. Does not check if brush requires any changes.
     pub fn render_shapes_cached(&mut self, brush: &'a Brush, buffer: &'a ShapeBuffer) {
+        self.set_brush(brush);
+        self.draw_buffer(buffer, DrawRange::from(buffer));
+    }
+
+    /// Draw multiple batches with the same brush, binding its pipeline and groups only once. All
+    /// buffers must use the same vertex layout, the one expected by the brush.
+    pub fn render_many(
+        &mut self, wnd: &GpuCtx, brush: &'a mut Brush, buffers: &[&'a ShapeBuffer],
+    ) -> Result<(), LError> {
+        if brush.is_outdated(wnd) {
+            brush.update(wnd)?;
+        }
+        self.set_brush(brush);
+        for buffer in buffers {
+            self.draw_buffer(buffer, DrawRange::from(*buffer));
+        }
+        Ok(())
+    }
+
+    /// Draw part of multiple batches with the same brush, binding its pipeline and groups only
+    /// once. All buffers must use the same vertex layout, the one expected by the brush.
+    pub fn render_batches(
+        &mut self, wnd: &GpuCtx, brush: &'a mut Brush, batches: &[(&'a ShapeBuffer, DrawRange)],
+    ) -> Result<(), LError> {
+        if brush.is_outdated(wnd) {
+            brush.update(wnd)?;
+        }
+        self.set_brush(brush);
+        for (buffer, range) in batches {
+            self.draw_buffer(buffer, range.clone());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_brush(&mut self, brush: &'a Brush) {
         let pipeline = brush.get_pipeline();
         self.pass.set_pipeline(pipeline);
         let bind_groups = brush.get_bind_groups();
         bind_groups
             .iter()
             .for_each(|(id, bg)| self.pass.set_bind_group(*id, bg, &[]));
+    }
 
+    pub(crate) fn draw_buffer(&mut self, buffer: &'a ShapeBuffer, range: DrawRange) {
         self.pass
             .set_vertex_buffer(0, buffer.vertex_buffer.slice(..));
         self.pass
-            .set_index_buffer(buffer.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        self.pass.draw_indexed(0..buffer.index_len, 0, 0..1);
+            .set_index_buffer(buffer.index_buffer.slice(..), buffer.index_format);
+        if let Some(instance_buffer) = &buffer.instance_buffer {
+            self.pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        }
+        self.pass.draw_indexed(range.indices, 0, range.instances);
     }
     pub fn finish(self) {
     }
@@ -1,25 +1,81 @@
+use std::rc::Rc;
+
 use wgpu::{ShaderModel, ShaderStages};
 
-use crate::{GpuCtx, ToBinder};
+use crate::{GpuCtx, ResourceId, ToBinder};
 
-/// Representation on how a texture will be drawn into a shape.
+/// Filtering and wrapping configuration for a sampler.
+#[derive(Clone, Copy, Debug)]
+pub struct SamplerDesc {
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    /// Wrapping used on all axes.
+    pub address_mode: wgpu::AddressMode,
+}
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// Representation on how a texture will be drawn into a shape. Cloning it shares the same GPU
+/// sampler, so it can be bound to several brushes.
+#[derive(Clone)]
 pub struct Sampler {
-    inner_sampler: wgpu::Sampler,
+    pub desc: SamplerDesc,
+    inner_sampler: Rc<wgpu::Sampler>,
+    id: ResourceId,
 }
 impl Sampler {
-    pub fn new_default(gpu: &GpuCtx) -> Self {
+    pub fn new(gpu: &GpuCtx, desc: SamplerDesc) -> Self {
         Self {
-            inner_sampler: gpu.device.create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
+            inner_sampler: Rc::new(gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: desc.address_mode,
+                address_mode_v: desc.address_mode,
+                address_mode_w: desc.address_mode,
+                mag_filter: desc.mag_filter,
+                min_filter: desc.min_filter,
+                mipmap_filter: desc.mipmap_filter,
+                ..Default::default()
+            })),
+            desc,
+            id: ResourceId::unique(),
+        }
+    }
+
+    /// Linear filtering clamping coordinates to the edges.
+    pub fn linear_clamp(gpu: &GpuCtx) -> Self {
+        Self::new(
+            gpu,
+            SamplerDesc {
                 mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                address_mode: wgpu::AddressMode::ClampToEdge,
+            },
+        )
+    }
+
+    /// Nearest filtering repeating the texture outside its bounds. Useful for pixel art.
+    pub fn nearest_repeat(gpu: &GpuCtx) -> Self {
+        Self::new(
+            gpu,
+            SamplerDesc {
+                mag_filter: wgpu::FilterMode::Nearest,
                 min_filter: wgpu::FilterMode::Nearest,
                 mipmap_filter: wgpu::FilterMode::Nearest,
-                ..Default::default()
-            }),
-        }
+                address_mode: wgpu::AddressMode::Repeat,
+            },
+        )
     }
+
+    pub fn new_default(gpu: &GpuCtx) -> Self { Self::new(gpu, SamplerDesc::default()) }
 }
 impl ToBinder for Sampler {
     fn get_layout(&self, index: u32) -> wgpu::BindGroupLayoutEntry {
@@ -38,5 +94,25 @@ impl ToBinder for Sampler {
         }
     }
 
+    fn resource_id(&self) -> ResourceId { self.id }
+
     fn debug_name(&self) -> &'static str { "Sampler" }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_test_gpu;
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn clones_share_resource() {
+        with_test_gpu(|gpu| {
+            let sampler = Sampler::nearest_repeat(gpu);
+            let clone = sampler.clone();
+            assert_eq!(sampler.resource_id(), clone.resource_id());
+            assert!(Rc::ptr_eq(&sampler.inner_sampler, &clone.inner_sampler));
+            assert_ne!(sampler.resource_id(), Sampler::nearest_repeat(gpu).resource_id());
+        });
+    }
+}
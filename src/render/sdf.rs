@@ -0,0 +1,41 @@
+use std::error::Error;
+
+use glam::Vec3;
+
+use crate::{BlendMode, Brush, BrushDesc, Color, GpuCtx, ShapeBatch};
+
+impl ShapeBatch {
+    ///Add a circle drawn as a single quad, to be used with `Brush::sdf_circle`. The brush computes
+    ///the circle edge per pixel, so it stays smooth and anti-aliased at any size without a segment
+    ///count.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use glam::Vec3;
+    /// use tridify_rs::*;
+    /// let mut batch = ShapeBatch::new();
+    /// batch.add_sdf_circle(Vec3::ZERO, 0.5, Color::RED);
+    /// assert_eq!(batch.vertices.len(), 4);
+    /// ```
+    pub fn add_sdf_circle(&mut self, center: Vec3, radius: f32, color: Color) -> &mut ShapeBatch {
+        self.add_2d_square(center, radius * 2.0, radius * 2.0, color)
+    }
+}
+
+impl Brush {
+    /// Create brush drawing circles added with `ShapeBatch::add_sdf_circle`. Positions are used as
+    /// they are, in clip space. Coverage is computed from the distance to the circle edge and
+    /// alpha blended, so shapes drawn with it should be drawn after the ones behind them.
+    pub fn sdf_circle(gpu: &GpuCtx) -> Result<Brush, Box<dyn Error>> {
+        Brush::from_source(
+            BrushDesc {
+                blend: BlendMode::AlphaBlend,
+                cull_mode: None,
+                sample_count: gpu.sample_count(),
+                ..Default::default()
+            },
+            gpu,
+            include_str!("sdf_circle.wgsl").to_string(),
+        )
+    }
+}
@@ -0,0 +1,190 @@
+use std::error::Error;
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use wgpu::{BufferAddress, BufferUsages, VertexAttribute, VertexBufferLayout};
+
+use crate::{
+    catch_gpu_error, reserve_buffer, BlendMode, Brush, BrushDesc, Color, GpuCtx, LError, Rect,
+    RenderPass, Sampler, ShapeBatch, ShapeBuffer, Texture,
+};
+
+/// Per sprite data read by `Brush::sprite`, one instance of a unit quad per sprite.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Zeroable)]
+pub struct SpriteInstance {
+    /// Bottom left corner and size of the sprite, in clip space.
+    pub dest: [f32; 4],
+    /// Top left corner and size of the atlas region drawn, in texture coordinates from 0 to 1.
+    pub uv_rect: [f32; 4],
+    pub tint: Color,
+}
+unsafe impl Pod for SpriteInstance {
+}
+
+impl SpriteInstance {
+    /// Layout of the sprite instance buffer, using shader locations 4 to 6 after the ones used by
+    /// `Vertex::DESC`.
+    pub const DESC: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<SpriteInstance>() as BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &[
+            VertexAttribute {
+                offset: 0,
+                shader_location: 4,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[f32; 4]>() as BufferAddress,
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[[f32; 4]; 2]>() as BufferAddress,
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    };
+
+    pub fn new(dest_rect: &Rect, uv_rect: &Rect, tint: Color) -> Self {
+        Self {
+            dest: [
+                dest_rect.pos.x,
+                dest_rect.pos.y,
+                dest_rect.size.x,
+                dest_rect.size.y,
+            ],
+            uv_rect: [uv_rect.pos.x, uv_rect.pos.y, uv_rect.size.x, uv_rect.size.y],
+            tint,
+        }
+    }
+}
+
+/// Sprites from a single texture atlas drawn together in one draw call, such as the tiles of a
+/// tilemap. Every sprite is an instance of the same quad, so only their position, atlas region
+/// and tint are sent to the GPU.
+///
+/// Sprites are kept between frames and only uploaded again after they change, so static grids
+/// can be added once and flushed every frame.
+///
+/// #Examples
+/// ``` no_run
+/// use glam::Vec2;
+/// use tridify_rs::*;
+/// fn draw(gpu: &GpuCtx, atlas: &Texture) -> Result<(), Box<dyn std::error::Error>> {
+///     let mut brush = Brush::sprite(gpu, atlas, &Sampler::nearest_repeat(gpu))?;
+///     let mut sprites = SpriteBatch::new(gpu)?;
+///     //Left half of the atlas drawn in the center of the screen.
+///     sprites.add(
+///         Rect::new(Vec2::splat(-0.5), Vec2::ONE),
+///         Rect::new(Vec2::ZERO, Vec2::new(0.5, 1.0)),
+///         Color::WHITE,
+///     );
+///     let mut builder = gpu.create_render_builder()?;
+///     let mut pass = builder.build_render_pass(RenderOptions::default());
+///     sprites.flush(gpu, &mut pass, &mut brush)?;
+///     pass.finish();
+///     builder.finish_render(gpu)?;
+///     Ok(())
+/// }
+/// ```
+pub struct SpriteBatch {
+    quad: ShapeBuffer,
+    sprites: Vec<SpriteInstance>,
+    needs_upload: bool,
+}
+impl SpriteBatch {
+    pub fn new(gpu: &GpuCtx) -> Result<Self, LError> {
+        let quad = ShapeBatch::new()
+            .add_2d_square(Vec3::new(0.5, 0.5, 0.0), 1.0, 1.0, Color::WHITE)
+            .bake_buffers(gpu)?;
+        Ok(Self {
+            quad,
+            sprites: Vec::new(),
+            needs_upload: false,
+        })
+    }
+
+    /// Add a sprite drawing the `uv_rect` region of the atlas, with its origin at the top left
+    /// corner, into `dest_rect` in clip space, multiplied by `tint`.
+    pub fn add(&mut self, dest_rect: Rect, uv_rect: Rect, tint: Color) -> &mut SpriteBatch {
+        self.sprites
+            .push(SpriteInstance::new(&dest_rect, &uv_rect, tint));
+        self.needs_upload = true;
+        self
+    }
+
+    /// Remove all sprites.
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+        self.needs_upload = true;
+    }
+
+    pub fn len(&self) -> usize { self.sprites.len() }
+    pub fn is_empty(&self) -> bool { self.sprites.is_empty() }
+
+    /// Draw all sprites with a single draw call, uploading them first if they changed. The brush
+    /// needs `BrushDesc::instanced` with `SpriteInstance::DESC`, like `Brush::sprite`.
+    pub fn flush<'a>(
+        &'a mut self, gpu: &GpuCtx, pass: &mut RenderPass<'a>, brush: &'a mut Brush,
+    ) -> Result<(), LError> {
+        if self.needs_upload {
+            self.upload(gpu)?;
+        }
+        if self.sprites.is_empty() {
+            return Ok(());
+        }
+        pass.render_shapes(gpu, brush, &self.quad)
+    }
+
+    fn upload(&mut self, gpu: &GpuCtx) -> Result<(), LError> {
+        let data: &[u8] = bytemuck::cast_slice(&self.sprites);
+        match &mut self.quad.instance_buffer {
+            Some(buffer) => reserve_buffer(gpu, buffer, data.len() as u64)?,
+            None => {
+                let buffer = catch_gpu_error(&gpu.device, || {
+                    gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Sprite instances"),
+                        size: data.len() as u64,
+                        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    })
+                })
+                .map_err(|e| LError::from_gpu(e, LError::BufferCreation))?;
+                self.quad.instance_buffer = Some(buffer);
+            }
+        }
+        if let Some(buffer) = &self.quad.instance_buffer {
+            gpu.queue.write_buffer(buffer, 0, data);
+        }
+        self.quad.instance_len = self.sprites.len() as u32;
+        self.needs_upload = false;
+        Ok(())
+    }
+}
+
+impl Brush {
+    /// Create brush drawing a `SpriteBatch` with the given atlas. Sprites are alpha blended and
+    /// positioned in clip space.
+    pub fn sprite(
+        gpu: &GpuCtx, atlas: &Texture, sampler: &Sampler,
+    ) -> Result<Brush, Box<dyn Error>> {
+        let mut brush = Brush::from_source(
+            BrushDesc {
+                blend: BlendMode::AlphaBlend,
+                cull_mode: None,
+                sample_count: gpu.sample_count(),
+                instanced: true,
+                instance_layout: SpriteInstance::DESC,
+                ..Default::default()
+            },
+            gpu,
+            include_str!("sprite.wgsl").to_string(),
+        )?;
+        brush.bind(0, 0, atlas.clone());
+        brush.bind(0, 1, sampler.clone());
+        Ok(brush)
+    }
+}
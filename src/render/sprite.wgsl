@@ -0,0 +1,36 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct SpriteInput {
+    @location(4) dest: vec4<f32>,
+    @location(5) uv_rect: vec4<f32>,
+    @location(6) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@group(0) @binding(0) var t_atlas: texture_2d<f32>;
+@group(0) @binding(1) var s_atlas: sampler;
+
+@vertex
+fn vs_main(model: VertexInput, sprite: SpriteInput) -> VertexOutput {
+    var out: VertexOutput;
+    //Quad goes from 0 to 1, with Y up in clip space and down in the texture.
+    let position = sprite.dest.xy + model.position.xy * sprite.dest.zw;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.color = model.color * sprite.tint;
+    out.uv = sprite.uv_rect.xy + vec2<f32>(model.uv.x, 1.0 - model.uv.y) * sprite.uv_rect.zw;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_atlas, s_atlas, in.uv) * in.color;
+}
@@ -0,0 +1,128 @@
+use std::{marker::PhantomData, mem::size_of, rc::Rc};
+
+use bytemuck::Pod;
+use wgpu::{util::DeviceExt, Buffer, ShaderStages};
+
+use crate::{read_buffer, GpuCtx, ResourceId, ToBinder};
+
+/// Typed handle to a GPU storage buffer, used to share large arrays with shaders. Unlike uniforms,
+/// shaders can write into them unless bound as read only.
+///
+/// The default WebGL2 limits don't allow storage buffers, so `TridifyOptions::limits` need to be
+/// raised, for example to `Limits::downlevel_defaults()`.
+///
+/// #Examples
+/// ``` no_run
+/// use tridify_rs::*;
+/// fn bind_particles(gpu: &GpuCtx, brush: &mut Brush) {
+///     let positions = vec![[0.0f32; 4]; 1024];
+///     //Matches `@group(0) @binding(0) var<storage, read> positions: array<vec4<f32>>;`
+///     let buffer = StorageBuffer::new(gpu, &positions, true, wgpu::ShaderStages::VERTEX);
+///     brush.bind(0, 0, buffer.clone());
+///
+///     //Data can be read back after shaders have modified it.
+///     let positions = buffer.read_back(gpu);
+/// }
+/// ```
+pub struct StorageBuffer<T: Pod> {
+    buffer: Rc<Buffer>,
+    id: ResourceId,
+    len: usize,
+    read_only: bool,
+    visibility: ShaderStages,
+    _value: PhantomData<T>,
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    /// Creates a new storage buffer with the elements provided. Writable buffers can't be visible
+    /// in the vertex stage.
+    pub fn new(gpu: &GpuCtx, data: &[T], read_only: bool, visibility: ShaderStages) -> Self {
+        let buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(data),
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+            });
+        Self {
+            buffer: Rc::new(buffer),
+            id: ResourceId::unique(),
+            len: data.len(),
+            read_only,
+            visibility,
+            _value: PhantomData,
+        }
+    }
+
+    /// Creates a new storage buffer with `len` zeroed elements.
+    pub fn zeroed(gpu: &GpuCtx, len: usize, read_only: bool, visibility: ShaderStages) -> Self {
+        Self::new(gpu, &vec![T::zeroed(); len], read_only, visibility)
+    }
+
+    /// Amount of elements in the buffer.
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Queues a write to update the buffer GPU data, starting at the element index `offset`.
+    pub fn update(&self, gpu: &GpuCtx, offset: usize, data: &[T]) {
+        gpu.queue.write_buffer(
+            &self.buffer,
+            (offset * size_of::<T>()) as u64,
+            bytemuck::cast_slice(data),
+        );
+    }
+
+    /// Copy buffer contents back to the CPU. Blocks until the GPU has finished all the work
+    /// submitted.
+    pub fn read_back(&self, gpu: &GpuCtx) -> Vec<T> {
+        let bytes = read_buffer(gpu, &self.buffer, (self.len * size_of::<T>()) as u64);
+        //Mapped bytes have no alignment guarantees, so copy them instead of casting.
+        let mut values = vec![T::zeroed(); self.len];
+        bytemuck::cast_slice_mut(&mut values).copy_from_slice(&bytes);
+        values
+    }
+}
+
+impl<T: Pod> ToBinder for StorageBuffer<T> {
+    fn get_layout(&self, index: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: index,
+            visibility: self.visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage {
+                    read_only: self.read_only,
+                },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+
+    fn get_group(&self, index: u32) -> wgpu::BindGroupEntry {
+        wgpu::BindGroupEntry {
+            binding: index,
+            resource: self.buffer.as_entire_binding(),
+        }
+    }
+
+    fn resource_id(&self) -> ResourceId { self.id }
+
+    fn debug_name(&self) -> &'static str { "Storage Buffer" }
+}
+
+impl<T: Pod> Clone for StorageBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: Rc::clone(&self.buffer),
+            id: self.id,
+            len: self.len,
+            read_only: self.read_only,
+            visibility: self.visibility,
+            _value: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{channel, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+use winit::event_loop::{EventLoop, EventLoopBuilder};
+
+use crate::{GpuCtx, Tridify};
+
+type GpuJob = Box<dyn FnOnce(&GpuCtx) + Send>;
+
+static GPU_THREAD: Mutex<Option<Sender<GpuJob>>> = Mutex::new(None);
+
+/// Run `f` with the GPU context of a hidden window shared by all tests. Only one event loop can
+/// be created per process, so every test runs on the same thread, one at a time. Panics if there
+/// is no display or adapter, so tests using it are marked `#[ignore]` and run with
+/// `cargo test -- --ignored` on a machine with both.
+pub(crate) fn with_test_gpu(f: impl FnOnce(&GpuCtx) + Send + 'static) {
+    let (done_tx, done_rx) = channel();
+    let job: GpuJob = Box::new(move |gpu| {
+        done_tx
+            .send(panic::catch_unwind(AssertUnwindSafe(|| f(gpu))))
+            .ok();
+    });
+    GPU_THREAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(spawn_gpu_thread)
+        .send(job)
+        .expect("GPU test thread has stopped.");
+    //The sender is dropped without a result when the thread couldn't create a window.
+    match done_rx.recv() {
+        Ok(Ok(())) => {}
+        Ok(Err(panic)) => panic::resume_unwind(panic),
+        Err(_) => panic!("GPU tests need a display and a GPU adapter."),
+    }
+}
+
+fn spawn_gpu_thread() -> Sender<GpuJob> {
+    let (tx, rx) = channel::<GpuJob>();
+    thread::spawn(move || {
+        //Creating the event loop panics without a display.
+        let mut app = panic::catch_unwind(build_test_event_loop)
+            .ok()
+            .map(|event_loop| Tridify::with_event_loop(Default::default(), event_loop));
+        let window = app.as_mut().and_then(|app| app.create_window().ok());
+        if let Some(window) = &window {
+            window.ctx.winit_wnd.set_visible(false);
+        }
+        for job in rx {
+            if let Some(window) = &window {
+                job(window.ctx());
+            }
+        }
+    });
+    tx
+}
+
+fn build_test_event_loop() -> EventLoop<()> {
+    let mut builder = EventLoopBuilder::new();
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    winit::platform::unix::EventLoopBuilderExtUnix::with_any_thread(&mut builder, true);
+    #[cfg(target_os = "windows")]
+    winit::platform::windows::EventLoopBuilderExtWindows::with_any_thread(&mut builder, true);
+    builder.build()
+}
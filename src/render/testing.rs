@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use glam::UVec2;
+use image::RgbaImage;
+use wgpu::TextureFormat;
+
+use crate::{GpuCtx, RenderPassBuilder, Texture, TextureDesc, TextureSize, TextureUsage};
+
+/// Draw into an offscreen `Rgba8Unorm` texture of the given size and read it back as an image,
+/// meant for pixel comparison tests together with `assert_images_close`.
+///
+/// The closure receives a render builder targeting the texture and builds the passes it needs,
+/// which are submitted afterwards. Reading back waits for all the GPU work, so the image is always
+/// complete.
+///
+/// #Examples
+/// ``` no_run
+/// use glam::UVec2;
+/// use tridify_rs::*;
+/// fn test_clear(gpu: &GpuCtx, brush: &mut Brush, shapes: &ShapeBuffer) {
+///     let image = render_to_image(gpu, UVec2::new(64, 64), |gpu, builder| {
+///         let mut pass = builder.build_render_pass(RenderOptions {
+///             clear_color: Some(Color::WHITE),
+///             ..Default::default()
+///         });
+///         pass.render_shapes(gpu, brush, shapes)?;
+///         pass.finish();
+///         Ok(())
+///     })
+///     .unwrap();
+///     let expected = image::open("tests/expected.png").unwrap().to_rgba8();
+///     assert_images_close(&image, &expected, 2);
+/// }
+/// ```
+pub fn render_to_image(
+    gpu: &GpuCtx, size: UVec2,
+    render: impl FnOnce(&GpuCtx, &mut RenderPassBuilder) -> Result<(), Box<dyn Error>>,
+) -> Result<RgbaImage, Box<dyn Error>> {
+    let target = Texture::new(
+        gpu,
+        TextureDesc {
+            size: TextureSize::D2(size.max(UVec2::ONE)),
+            usage: TextureUsage::RENDER | TextureUsage::SOURCE,
+            format: TextureFormat::Rgba8Unorm,
+            mip_level_count: 1,
+        },
+        Some("Test render texture"),
+    );
+    let mut builder = RenderPassBuilder::from_texture(gpu, &target)?;
+    render(gpu, &mut builder)?;
+    builder.finish_render(gpu)?;
+    let size = target.desc.size.get_size();
+    let image = RgbaImage::from_raw(size.x, size.y, target.read_pixels(gpu)?)
+        .ok_or("Texture data doesn't match its size.")?;
+    Ok(image)
+}
+
+/// Panic if the images have different sizes or if any channel of any pixel differs by more than
+/// `tolerance`, reporting the first pixel that doesn't match.
+///
+/// #Examples
+/// ```
+/// use image::{Rgba, RgbaImage};
+/// use tridify_rs::assert_images_close;
+/// let a = RgbaImage::from_pixel(2, 2, Rgba([100, 150, 200, 255]));
+/// let b = RgbaImage::from_pixel(2, 2, Rgba([101, 149, 200, 255]));
+/// assert_images_close(&a, &b, 1);
+/// ```
+pub fn assert_images_close(a: &RgbaImage, b: &RgbaImage, tolerance: u8) {
+    assert_eq!(
+        a.dimensions(),
+        b.dimensions(),
+        "Images have different sizes."
+    );
+    for ((x, y, pa), pb) in a.enumerate_pixels().zip(b.pixels()) {
+        let close =
+            pa.0.iter()
+                .zip(pb.0.iter())
+                .all(|(ca, cb)| ca.abs_diff(*cb) <= tolerance);
+        assert!(
+            close,
+            "Pixel ({}, {}) differs: {:?} != {:?} with tolerance {}.",
+            x, y, pa.0, pb.0, tolerance
+        );
+    }
+}
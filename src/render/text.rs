@@ -0,0 +1,286 @@
+use std::error::Error;
+
+use glam::{Mat4, UVec2, Vec2, Vec3};
+
+use crate::{
+    BlendMode, Brush, BrushDesc, Color, GpuCtx, LError, Rect, RenderOptions, RenderPassBuilder,
+    Sampler, SamplerDesc, ShapeBatch, ShapeBuffer, Texture, TextureDesc, TextureSize, TextureUsage,
+    Vertex,
+};
+
+/// Size in pixels of each glyph in the embedded font.
+pub const GLYPH_SIZE: u32 = 8;
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+const FIRST_GLYPH: char = ' ';
+/// Atlas cell used for characters not included in the font.
+const MISSING_GLYPH: u32 = 95;
+const MISSING_GLYPH_BITMAP: [u8; 8] = [0x7F, 0x41, 0x41, 0x41, 0x41, 0x41, 0x7F, 0x00];
+
+/// Monospace 8x8 bitmap font covering printable ASCII characters. Each byte is a row from top to
+/// bottom, with the least significant bit being the leftmost pixel.
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // !
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // #
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // $
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // %
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // &
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // (
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // )
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // *
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ,
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // .
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // /
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // 0
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // 1
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // 2
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // 3
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // 4
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // 5
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // 6
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // 7
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // 8
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ;
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // <
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // =
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // >
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // ?
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // @
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // A
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // B
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // C
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // D
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // E
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // F
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // G
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // H
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // I
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // J
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // K
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // L
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // M
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // N
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // O
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // P
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // Q
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // R
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // S
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // T
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // V
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // W
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // X
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // Y
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // Z
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // [
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // \
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ]
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // _
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // a
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // b
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // c
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // d
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // e
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // f
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // g
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // h
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // i
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // j
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // k
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // l
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // m
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // n
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // o
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // p
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // q
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // r
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // s
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // t
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // u
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // v
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // w
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // x
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // y
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // z
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // {
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // |
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // }
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ~
+];
+
+/// Create a texture atlas with all the glyphs from the embedded font, white where the glyph is
+/// drawn and transparent elsewhere.
+pub fn create_font_atlas(gpu: &GpuCtx) -> Result<Texture, LError> {
+    let size = UVec2::new(ATLAS_COLUMNS, ATLAS_ROWS) * GLYPH_SIZE;
+    let mut pixels = vec![0u8; (size.x * size.y * 4) as usize];
+    let bitmaps = FONT.iter().chain(std::iter::once(&MISSING_GLYPH_BITMAP));
+    for (i, bitmap) in bitmaps.enumerate() {
+        let cell = UVec2::new(i as u32 % ATLAS_COLUMNS, i as u32 / ATLAS_COLUMNS) * GLYPH_SIZE;
+        for (y, row) in bitmap.iter().enumerate() {
+            for x in 0..GLYPH_SIZE {
+                if row & (1 << x) == 0 {
+                    continue;
+                }
+                let pixel = (cell.y + y as u32) * size.x + cell.x + x;
+                let index = pixel as usize * 4;
+                pixels[index..index + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+    }
+    Texture::init(
+        gpu,
+        TextureDesc {
+            size: TextureSize::D2(size),
+            usage: TextureUsage::TEXTURE_BIND | TextureUsage::DESTINATION,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            mip_level_count: 1,
+        },
+        &pixels,
+        Some("Font atlas"),
+    )
+}
+
+/// Region of the font atlas in UV space containing the glyph. Characters without glyph use a box.
+pub fn glyph_uv_rect(c: char) -> Rect {
+    let index = (c as u32)
+        .checked_sub(FIRST_GLYPH as u32)
+        .filter(|i| (*i as usize) < FONT.len())
+        .unwrap_or(MISSING_GLYPH);
+    let cell_size = Vec2::new(1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32);
+    let top_left = Vec2::new(
+        (index % ATLAS_COLUMNS) as f32,
+        (index / ATLAS_COLUMNS) as f32,
+    ) * cell_size;
+    //Texture rows go downwards, so the rect starts at the bottom of the cell and grows upwards.
+    Rect::new(
+        top_left + Vec2::new(0.0, cell_size.y),
+        Vec2::new(cell_size.x, -cell_size.y),
+    )
+}
+
+impl ShapeBatch {
+    ///Add a line of text using the embedded font, one textured quad per glyph. `origin` sets the
+    /// top left corner of the text and its color, `size` the width and height of each glyph.
+    /// Newlines move the next glyphs one line down.
+    pub fn add_text(&mut self, text: &str, origin: Vertex, size: f32) -> &mut ShapeBatch {
+        let mut cursor = Vec2::new(origin.x(), origin.y());
+        for c in text.chars() {
+            if c == '\n' {
+                cursor = Vec2::new(origin.x(), cursor.y - size);
+                continue;
+            }
+            if c != ' ' {
+                let corner = |offset: Vec2| Vertex {
+                    pos: [cursor.x + offset.x, cursor.y + offset.y, origin.pos[2]],
+                    ..origin
+                };
+                self.add_textured_quad(
+                    [
+                        corner(Vec2::new(0.0, -size)),
+                        corner(Vec2::new(size, -size)),
+                        corner(Vec2::ZERO),
+                        corner(Vec2::new(size, 0.0)),
+                    ],
+                    &glyph_uv_rect(c),
+                );
+            }
+            cursor.x += size;
+        }
+        self
+    }
+}
+
+impl Brush {
+    /// Create brush drawing text added with `ShapeBatch::add_text`. Positions are used as they are,
+    /// in clip space. Font atlas and sampler are bound to group 0, with pixels outside the glyphs
+    /// discarded.
+    pub fn text(gpu: &GpuCtx) -> Result<Brush, Box<dyn Error>> {
+        let mut brush = Brush::from_source(
+            BrushDesc {
+                blend: BlendMode::AlphaBlend,
+                cull_mode: None,
+                sample_count: gpu.sample_count(),
+                ..Default::default()
+            },
+            gpu,
+            include_str!("text.wgsl").to_string(),
+        )?;
+        brush.bind(0, 0, create_font_atlas(gpu)?);
+        brush.bind(
+            0,
+            1,
+            Sampler::new(
+                gpu,
+                SamplerDesc {
+                    mag_filter: wgpu::FilterMode::Nearest,
+                    min_filter: wgpu::FilterMode::Nearest,
+                    ..Default::default()
+                },
+            ),
+        );
+        Ok(brush)
+    }
+}
+
+/// Frame stats drawn in the top left corner of a window, see `WindowSettings::show_stats`.
+#[derive(Default)]
+pub(crate) struct StatsOverlay {
+    pub(crate) text: String,
+    brush: Option<Brush>,
+    buffer: Option<ShapeBuffer>,
+}
+impl StatsOverlay {
+    /// Glyph size and margin from the window corner, in pixels.
+    const GLYPH_PIXELS: f32 = 16.0;
+
+    /// Draw the stats on top of the builder targets in their own pass, keeping existing contents.
+    pub(crate) fn draw(
+        &mut self, gpu: &GpuCtx, builder: &mut RenderPassBuilder,
+    ) -> Result<(), LError> {
+        //Stats are only known after the first second.
+        if self.text.is_empty() {
+            return Ok(());
+        }
+        //Lay out the text in pixels with Y up, then map to clip space so glyphs stay square.
+        let size = builder.target_size.as_vec2();
+        let mut batch = ShapeBatch::new();
+        batch
+            .add_text(
+                &self.text,
+                Vertex::new(0.0, 0.0, 0.0, Some(Color::WHITE), None),
+                Self::GLYPH_PIXELS,
+            )
+            .transform(
+                Mat4::from_translation(Vec3::new(
+                    -1.0 + 2.0 * Self::GLYPH_PIXELS / size.x,
+                    1.0 - 2.0 * Self::GLYPH_PIXELS / size.y,
+                    0.0,
+                )) * Mat4::from_scale(Vec3::new(2.0 / size.x, 2.0 / size.y, 1.0)),
+            );
+        if self.brush.is_none() {
+            self.brush = Some(Brush::text(gpu).map_err(|e| LError::ShaderCompile(e.to_string()))?);
+        }
+        match &mut self.buffer {
+            Some(buffer) => buffer.update(gpu, &batch)?,
+            None => self.buffer = Some(batch.bake_buffers(gpu)?),
+        }
+        if let (Some(brush), Some(buffer)) = (&mut self.brush, &self.buffer) {
+            let mut pass = builder.build_render_pass(RenderOptions {
+                clear_color: None,
+                depth: false,
+            });
+            pass.render_shapes(gpu, brush, buffer)?;
+            pass.finish();
+        }
+        Ok(())
+    }
+}
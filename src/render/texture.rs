@@ -1,13 +1,15 @@
-use std::{num::NonZeroU32, path::Path, rc::Rc};
+use std::{borrow::Cow, error::Error, num::NonZeroU32, path::Path, rc::Rc};
 
 use egui::Vec2;
 use glam::{UVec2, UVec3};
 use wgpu::{
-    ImageCopyTexture, ImageDataLayout, ShaderStages, TextureAspect, TextureDescriptor,
-    TextureFormat, TextureUsages, TextureViewDescriptor,
+    CommandEncoderDescriptor, FragmentState, ImageCopyTexture, ImageDataLayout, MultisampleState,
+    Operations, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderStages, TextureAspect,
+    TextureDescriptor, TextureFormat, TextureUsages, TextureViewDescriptor, VertexState,
 };
 
-use crate::{GpuCtx, ToBinder};
+use crate::{map_read, Color, GpuCtx, LError, ResourceId, ToBinder};
 
 bitflags::bitflags! {
     /// Specifies how the texture will be used for optimizations.
@@ -20,11 +22,17 @@ bitflags::bitflags! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextureSize {
     D1(u32),
     D2(UVec2),
     D3(UVec3),
+    /// Array of 2D textures with the same size, given the size and amount of layers.
+    D2Array(UVec2, u32),
+    /// Cube map with six square faces of the given size, stored as layers in +X, -X, +Y, -Y, +Z,
+    /// -Z order.
+    Cube(u32),
 }
 impl TextureSize {
     pub fn get_size(&self) -> UVec3 {
@@ -32,6 +40,8 @@ impl TextureSize {
             TextureSize::D1(x) => UVec3::new(*x, 1, 1),
             TextureSize::D2(size) => UVec3::new(size.x, size.y, 1),
             TextureSize::D3(size) => UVec3::new(size.x, size.y, size.z),
+            TextureSize::D2Array(size, layers) => UVec3::new(size.x, size.y, *layers),
+            TextureSize::Cube(size) => UVec3::new(*size, *size, 6),
         }
     }
     pub fn get_wgpu_dimension(&self) -> wgpu::TextureDimension {
@@ -39,6 +49,8 @@ impl TextureSize {
             TextureSize::D1(_) => wgpu::TextureDimension::D1,
             TextureSize::D2(_) => wgpu::TextureDimension::D2,
             TextureSize::D3(_) => wgpu::TextureDimension::D3,
+            TextureSize::D2Array(..) => wgpu::TextureDimension::D2,
+            TextureSize::Cube(_) => wgpu::TextureDimension::D2,
         }
     }
     pub fn get_wgpu_view_dimension(&self) -> wgpu::TextureViewDimension {
@@ -46,16 +58,44 @@ impl TextureSize {
             TextureSize::D1(_) => wgpu::TextureViewDimension::D1,
             TextureSize::D2(_) => wgpu::TextureViewDimension::D2,
             TextureSize::D3(_) => wgpu::TextureViewDimension::D3,
+            TextureSize::D2Array(..) => wgpu::TextureViewDimension::D2Array,
+            TextureSize::Cube(_) => wgpu::TextureViewDimension::Cube,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TextureDesc {
     pub size: TextureSize,
     pub usage: TextureUsage,
+    pub format: TextureFormat,
+    /// Amount of mip levels, including the full size one. Use `1` to disable mipmaps.
+    pub mip_level_count: u32,
 }
 impl TextureDesc {
+    /// Size in bytes of a single pixel for the texture format. For depth formats it's the size of
+    /// the depth value, as copied from or into buffers. Fails for formats that can't be copied,
+    /// such as `Depth24Plus` and `Depth24PlusStencil8`.
+    pub fn bytes_per_pixel(&self) -> Result<u32, LError> {
+        self.format
+            .block_size(Some(self.copy_aspect()))
+            .ok_or_else(|| {
+                LError::Validation(format!(
+                    "Texture format {:?} can't be copied to or from buffers.",
+                    self.format
+                ))
+            })
+    }
+
+    /// Aspect copied when reading or writing pixels. Depth formats only copy their depth values.
+    fn copy_aspect(&self) -> TextureAspect {
+        if self.format.has_depth_aspect() {
+            TextureAspect::DepthOnly
+        } else {
+            TextureAspect::All
+        }
+    }
+
     fn get_wgpu_usage(&self) -> TextureUsages {
         let mut usage = TextureUsages::empty();
         if self.usage.contains(TextureUsage::DESTINATION) {
@@ -77,31 +117,175 @@ impl TextureDesc {
     }
 }
 
-/// GPU texture handle.
-#[derive(Debug)]
+/// Bytes per row needed when copying texture data into a buffer. wgpu requires each row to be
+/// aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded = width * bytes_per_pixel;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (unpadded + align - 1) / align * align
+}
+
+/// Removes row padding from texture data copied into a buffer, returning tightly packed rows.
+pub fn unpad_rows(data: &[u8], bytes_per_row: usize, padded_bytes_per_row: usize) -> Vec<u8> {
+    if bytes_per_row == padded_bytes_per_row {
+        return data.to_vec();
+    }
+    data.chunks(padded_bytes_per_row)
+        .flat_map(|row| &row[..bytes_per_row])
+        .copied()
+        .collect()
+}
+
+/// Save tightly packed RGBA8 pixels as an image, with the file format chosen by the path extension.
+pub(crate) fn save_rgba8(path: &Path, size: UVec2, data: Vec<u8>) -> Result<(), Box<dyn Error>> {
+    let image = image::RgbaImage::from_raw(size.x, size.y, data)
+        .ok_or("Texture data doesn't match its size.")?;
+    let image = image::DynamicImage::ImageRgba8(image);
+    //JPEG has no alpha channel.
+    let is_jpeg = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| {
+            ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg")
+        });
+    if is_jpeg {
+        image.to_rgb8().save(path)?;
+    } else {
+        image.save(path)?;
+    }
+    Ok(())
+}
+
+/// Append the color bytes in the given texture format.
+fn pack_color(color: Color, format: TextureFormat, out: &mut Vec<u8>) -> Result<(), String> {
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+            out.extend([color.r, color.g, color.b, color.a].map(to_u8))
+        }
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+            out.extend([color.b, color.g, color.r, color.a].map(to_u8))
+        }
+        TextureFormat::R8Unorm => out.push(to_u8(color.r)),
+        TextureFormat::Rgba32Float => out.extend(bytemuck::bytes_of(&color)),
+        _ => return Err(format!("Texture format {:?} is not supported.", format)),
+    }
+    Ok(())
+}
+
+/// GPU texture handle. Cloning it shares the same GPU texture, so a texture rendered into can
+/// also be bound to a brush.
+#[derive(Clone, Debug)]
 pub struct Texture {
     pub desc: TextureDesc,
-    texture: Rc<wgpu::Texture>,
-    view: wgpu::TextureView,
+    pub(crate) texture: Rc<wgpu::Texture>,
+    view: Rc<wgpu::TextureView>,
+    id: ResourceId,
 }
 
 impl Texture {
     pub fn from_path(gpu: &GpuCtx, path: &Path) -> Self {
         let image =
             image::open(path).unwrap_or_else(|_| panic!("Error loading image at {:?}", path));
+        Self::from_image(gpu, &image)
+    }
+
+    /// Create texture decoding an encoded image (PNG, JPEG...) from memory.
+    pub fn from_bytes(gpu: &GpuCtx, bytes: &[u8]) -> Result<Self, image::ImageError> {
+        let image = image::load_from_memory(bytes)?;
+        Ok(Self::from_image(gpu, &image))
+    }
+
+    /// Create cube map loading each face from an image, in +X, -X, +Y, -Y, +Z, -Z order. All faces
+    /// need to be square and have the same size.
+    pub fn cube_from_paths(gpu: &GpuCtx, paths: [&Path; 6]) -> Result<Self, Box<dyn Error>> {
+        let faces = paths
+            .iter()
+            .map(|path| image::open(path).map(|image| image.to_rgba8()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let size = faces[0].width();
+        if faces
+            .iter()
+            .any(|face| face.width() != size || face.height() != size)
+        {
+            return Err("Cube map faces need to be square and have the same size.".into());
+        }
+
+        let desc = TextureDesc {
+            size: TextureSize::Cube(size),
+            usage: TextureUsage::TEXTURE_BIND | TextureUsage::DESTINATION,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+        };
+        let texture = Self::new(gpu, desc, None);
+        for (layer, face) in faces.iter().enumerate() {
+            texture.write_layer_pixels(gpu, layer as u32, face)?;
+        }
+        Ok(texture)
+    }
+
+    /// Create 2D texture evaluating the function for each pixel coordinate. Supported formats are
+    /// 8 bit RGBA and BGRA, `R8Unorm` and `Rgba32Float`.
+    ///
+    /// #Examples
+    /// ``` no_run
+    /// use glam::UVec2;
+    /// use tridify_rs::*;
+    /// fn checkerboard(gpu: &GpuCtx) -> Texture {
+    ///     Texture::from_fn(
+    ///         gpu,
+    ///         UVec2::new(64, 64),
+    ///         wgpu::TextureFormat::Rgba8UnormSrgb,
+    ///         |x, y| if (x / 8 + y / 8) % 2 == 0 { Color::WHITE } else { Color::BLACK },
+    ///     )
+    ///     .unwrap()
+    /// }
+    /// ```
+    pub fn from_fn(
+        gpu: &GpuCtx, size: UVec2, format: TextureFormat, f: impl Fn(u32, u32) -> Color,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut pixels = Vec::new();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                pack_color(f(x, y), format, &mut pixels)?;
+            }
+        }
+        let desc = TextureDesc {
+            size: TextureSize::D2(size),
+            usage: TextureUsage::TEXTURE_BIND | TextureUsage::DESTINATION,
+            format,
+            mip_level_count: 1,
+        };
+        Ok(Self::init(gpu, desc, &pixels, None)?)
+    }
+
+    fn from_image(gpu: &GpuCtx, image: &image::DynamicImage) -> Self {
         let desc = TextureDesc {
             size: TextureSize::D2(UVec2::new(image.width(), image.height())),
             usage: TextureUsage::TEXTURE_BIND | TextureUsage::DESTINATION,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
         };
         let texture = Self::new(gpu, desc, None);
-        texture.write_pixels(gpu, &image.to_rgba8());
+        //RGBA8 can always be copied, 4 bytes per pixel.
+        texture.queue_write(
+            gpu,
+            &image.to_rgba8(),
+            UVec3::ZERO,
+            texture.desc.size.get_size(),
+            4,
+        );
         texture
     }
 
-    pub fn init(gpu: &GpuCtx, desc: TextureDesc, data: &[u8], label: Option<&str>) -> Self {
+    /// Create texture and queue a write with its initial contents. Fails if the format can't be
+    /// written, see `Texture::write_pixels`.
+    pub fn init(
+        gpu: &GpuCtx, desc: TextureDesc, data: &[u8], label: Option<&str>,
+    ) -> Result<Self, LError> {
         let texture = Self::new(gpu, desc, label);
-        texture.write_pixels(gpu, data);
-        texture
+        texture.write_pixels(gpu, data)?;
+        Ok(texture)
     }
 
     pub fn new(gpu: &GpuCtx, desc: TextureDesc, label: Option<&str>) -> Self {
@@ -113,36 +297,176 @@ impl Texture {
                 height: size.y,
                 depth_or_array_layers: size.z,
             },
-            mip_level_count: 1,
+            mip_level_count: desc.mip_level_count.max(1),
             sample_count: 1,
             dimension: desc.size.get_wgpu_dimension(),
-            format: TextureFormat::Rgba8UnormSrgb,
+            format: desc.format,
             usage: desc.get_wgpu_usage(),
-            view_formats: &[TextureFormat::Rgba8UnormSrgb],
+            view_formats: &[desc.format],
+        });
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(desc.size.get_wgpu_view_dimension()),
+            ..Default::default()
         });
-        let view = texture.create_view(&TextureViewDescriptor::default());
         Self {
             desc,
             texture: Rc::new(texture),
-            view,
+            view: Rc::new(view),
+            id: ResourceId::unique(),
         }
     }
 
-    ///Queues a write into the texture
-    pub fn write_pixels(&self, gpu: &GpuCtx, data: &[u8]) {
-        let size = self.desc.size.get_size();
-        gpu.queue.write_texture(
+    /// Recreate the texture with a new size, keeping its format, usage and mip level count, for
+    /// example to match a resized window. Contents are discarded.
+    ///
+    /// Clones made before resizing, such as the ones bound to brushes, keep using the old texture,
+    /// so the texture needs to be bound again to every brush that should draw with the new one.
+    pub fn resize(&mut self, gpu: &GpuCtx, new_size: TextureSize) {
+        let desc = TextureDesc {
+            size: new_size,
+            ..self.desc.clone()
+        };
+        *self = Self::new(gpu, desc, None);
+    }
+
+    /// Generate all mip levels from the first one by downsampling each level into the next. Texture
+    /// needs `TextureUsage::RENDER | TextureUsage::TEXTURE_BIND` and a 2D size.
+    pub fn generate_mipmaps(&self, gpu: &GpuCtx) -> Result<(), Box<dyn Error>> {
+        if !self
+            .desc
+            .usage
+            .contains(TextureUsage::RENDER | TextureUsage::TEXTURE_BIND)
+        {
+            return Err("Generating mipmaps requires RENDER and TEXTURE_BIND usages.".into());
+        }
+        if !matches!(self.desc.size, TextureSize::D2(_)) {
+            return Err("Mipmaps can only be generated for 2D textures.".into());
+        }
+
+        let device = &gpu.device;
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Mipmap shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("blit.wgsl"))),
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Mipmap pipeline"),
+            layout: None,
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(self.texture.format().into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Mipmap encoder"),
+        });
+        let views = (0..self.texture.mip_level_count())
+            .map(|mip| {
+                self.texture.create_view(&TextureViewDescriptor {
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+        for target in 1..views.len() {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[target - 1]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            });
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &views[target],
+                    resolve_target: None,
+                    ops: Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        gpu.queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    /// Copy texture contents back to the CPU, returning tightly packed bytes in the texture format.
+    /// Texture needs `TextureUsage::SOURCE`. Blocks until the GPU has finished the copy and all the
+    /// work submitted before it, so passes drawing into the texture are always complete.
+    ///
+    /// Depth textures return only their depth values, for example one `f32` per pixel for
+    /// `Depth32Float`. Formats such as `Depth24Plus` can't be copied and return an error.
+    pub fn read_pixels(&self, gpu: &GpuCtx) -> Result<Vec<u8>, LError> {
+        self.read_region_pixels(gpu, UVec3::ZERO, self.desc.size.get_size())
+    }
+
+    /// Copy a subset of the texture back to the CPU, starting at `origin` with the given `size`.
+    /// Same requirements as `read_pixels`, useful to read a few pixels without copying the whole
+    /// texture. Fails if the region doesn't fit inside the texture.
+    pub fn read_region_pixels(
+        &self, gpu: &GpuCtx, origin: UVec3, size: UVec3,
+    ) -> Result<Vec<u8>, LError> {
+        self.check_region(origin, size)?;
+        let bytes_per_pixel = self.desc.bytes_per_pixel()?;
+        let bytes_per_row = size.x * bytes_per_pixel;
+        let padded_bytes_per_row = padded_bytes_per_row(size.x, bytes_per_pixel);
+        let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture readback buffer"),
+            size: (padded_bytes_per_row * size.y * size.z) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
             ImageCopyTexture {
                 texture: &self.texture,
                 mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: TextureAspect::All,
+                origin: wgpu::Origin3d {
+                    x: origin.x,
+                    y: origin.y,
+                    z: origin.z,
+                },
+                aspect: self.desc.copy_aspect(),
             },
-            data,
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(size.x * 4),
-                rows_per_image: Some(size.y),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.y),
+                },
             },
             wgpu::Extent3d {
                 width: size.x,
@@ -150,11 +474,102 @@ impl Texture {
                 depth_or_array_layers: size.z,
             },
         );
+        gpu.queue.submit(Some(encoder.finish()));
+
+        Ok(unpad_rows(
+            &map_read(gpu, &buffer),
+            bytes_per_row as usize,
+            padded_bytes_per_row as usize,
+        ))
+    }
+
+    /// Read texture contents back and save them as an image, with the file format chosen by the
+    /// path extension. Only 2D `Rgba8Unorm` and `Rgba8UnormSrgb` textures with
+    /// `TextureUsage::SOURCE` are supported.
+    pub fn save_to_path(&self, gpu: &GpuCtx, path: &Path) -> Result<(), Box<dyn Error>> {
+        if !matches!(
+            self.desc.format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        ) {
+            return Err(format!(
+                "Saving textures with format {:?} is not supported.",
+                self.desc.format
+            )
+            .into());
+        }
+        let size = match self.desc.size {
+            TextureSize::D2(size) => size,
+            _ => return Err("Only 2D textures can be saved.".into()),
+        };
+        save_rgba8(path, size, self.read_pixels(gpu)?)
     }
 
-    ///Queues a write into the texture updating only a subset of it
-    pub fn write_region_pixels(&self, gpu: &GpuCtx, data: &[u8], origin: UVec3) {
+    ///Queues a write into the texture. Fails for depth formats other than `Depth16Unorm`, which can
+    ///only be read back or can't be copied at all.
+    pub fn write_pixels(&self, gpu: &GpuCtx, data: &[u8]) -> Result<(), LError> {
+        self.write_region_pixels(gpu, data, UVec3::ZERO, self.desc.size.get_size())
+    }
+
+    ///Queues a write into a single layer of an array or cube texture.
+    pub fn write_layer_pixels(&self, gpu: &GpuCtx, layer: u32, data: &[u8]) -> Result<(), LError> {
         let size = self.desc.size.get_size();
+        self.write_region_pixels(
+            gpu,
+            data,
+            UVec3::new(0, 0, layer),
+            UVec3::new(size.x, size.y, 1),
+        )
+    }
+
+    ///Queues a write into the texture updating only a subset of it, starting at `origin` with the
+    ///given `size`. For array and cube textures `origin.z` is the first layer written. Fails if the
+    ///region doesn't fit inside the texture or `data` is smaller than the region.
+    pub fn write_region_pixels(
+        &self, gpu: &GpuCtx, data: &[u8], origin: UVec3, size: UVec3,
+    ) -> Result<(), LError> {
+        if self.desc.format.has_depth_aspect() && self.desc.format != TextureFormat::Depth16Unorm {
+            return Err(LError::Validation(format!(
+                "Texture format {:?} can't be written from the CPU.",
+                self.desc.format
+            )));
+        }
+        self.check_region(origin, size)?;
+        let bytes_per_pixel = self.desc.bytes_per_pixel()?;
+        let needed = size.x as usize * size.y as usize * size.z as usize * bytes_per_pixel as usize;
+        if data.len() < needed {
+            return Err(LError::Validation(format!(
+                "Writing a {}x{}x{} region needs {} bytes but only {} were given.",
+                size.x,
+                size.y,
+                size.z,
+                needed,
+                data.len()
+            )));
+        }
+        self.queue_write(gpu, data, origin, size, bytes_per_pixel);
+        Ok(())
+    }
+
+    /// Fail if the region starting at `origin` with the given `size` isn't inside the texture.
+    fn check_region(&self, origin: UVec3, size: UVec3) -> Result<(), LError> {
+        let texture_size = self.desc.size.get_size();
+        let inside = (0..3).all(|axis| {
+            origin[axis]
+                .checked_add(size[axis])
+                .map_or(false, |end| end <= texture_size[axis])
+        });
+        if !inside {
+            return Err(LError::Validation(format!(
+                "Region from {} with size {} is outside the texture size {}.",
+                origin, size, texture_size
+            )));
+        }
+        Ok(())
+    }
+
+    fn queue_write(
+        &self, gpu: &GpuCtx, data: &[u8], origin: UVec3, size: UVec3, bytes_per_pixel: u32,
+    ) {
         gpu.queue.write_texture(
             ImageCopyTexture {
                 texture: &self.texture,
@@ -164,12 +579,12 @@ impl Texture {
                     y: origin.y,
                     z: origin.z,
                 },
-                aspect: TextureAspect::All,
+                aspect: self.desc.copy_aspect(),
             },
             data,
             ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(size.x * 4),
+                bytes_per_row: Some(size.x * bytes_per_pixel),
                 rows_per_image: Some(size.y),
             },
             wgpu::Extent3d {
@@ -187,7 +602,11 @@ impl ToBinder for Texture {
             binding: index,
             visibility: ShaderStages::FRAGMENT,
             ty: wgpu::BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                sample_type: self
+                    .desc
+                    .format
+                    .sample_type(None)
+                    .unwrap_or(wgpu::TextureSampleType::Float { filterable: true }),
                 view_dimension: self.desc.size.get_wgpu_view_dimension(),
                 multisampled: false,
             },
@@ -202,5 +621,272 @@ impl ToBinder for Texture {
         }
     }
 
+    fn resource_id(&self) -> ResourceId { self.id }
+
     fn debug_name(&self) -> &'static str { "Texture" }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::with_test_gpu;
+
+    fn copy_texture(gpu: &GpuCtx, size: TextureSize, format: TextureFormat) -> Texture {
+        let desc = TextureDesc {
+            size,
+            usage: TextureUsage::DESTINATION | TextureUsage::SOURCE,
+            format,
+            mip_level_count: 1,
+        };
+        Texture::new(gpu, desc, None)
+    }
+
+    #[test]
+    fn padded_rows_round_trip() {
+        //70 RGBA8 pixels are 280 bytes, padded to the next multiple of 256.
+        assert_eq!(padded_bytes_per_row(70, 4), 512);
+        assert_eq!(padded_bytes_per_row(64, 4), 256);
+        assert_eq!(padded_bytes_per_row(5, 1), 256);
+
+        let rows: Vec<Vec<u8>> = (0..3u8).map(|y| vec![y + 1; 280]).collect();
+        let padded: Vec<u8> = rows
+            .iter()
+            .flat_map(|row| {
+                row.iter()
+                    .copied()
+                    .chain(std::iter::repeat(0).take(512 - 280))
+            })
+            .collect();
+        assert_eq!(unpad_rows(&padded, 280, 512), rows.concat());
+        //Rows without padding are returned as they are.
+        assert_eq!(unpad_rows(&rows.concat(), 280, 280), rows.concat());
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn write_r8_pixels() {
+        with_test_gpu(|gpu| {
+            //Odd width, so rows are neither 4 bytes per pixel nor aligned for copies.
+            let texture = copy_texture(
+                gpu,
+                TextureSize::D2(UVec2::new(5, 3)),
+                TextureFormat::R8Unorm,
+            );
+            let pixels: Vec<u8> = (0..15).map(|i| i * 10).collect();
+            texture.write_pixels(gpu, &pixels).unwrap();
+            assert_eq!(texture.read_pixels(gpu).unwrap(), pixels);
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn write_region_pixels_only_changes_region() {
+        with_test_gpu(|gpu| {
+            let texture = copy_texture(
+                gpu,
+                TextureSize::D2(UVec2::new(4, 4)),
+                TextureFormat::Rgba8Unorm,
+            );
+            texture.write_pixels(gpu, &[0; 4 * 4 * 4]).unwrap();
+            texture
+                .write_region_pixels(
+                    gpu,
+                    &[255; 2 * 2 * 4],
+                    UVec3::new(1, 2, 0),
+                    UVec3::new(2, 2, 1),
+                )
+                .unwrap();
+            let pixels = texture.read_pixels(gpu).unwrap();
+            for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+                let (x, y) = (i % 4, i / 4);
+                let inside = (1..3).contains(&x) && (2..4).contains(&y);
+                assert_eq!(
+                    pixel,
+                    [if inside { 255 } else { 0 }; 4],
+                    "pixel ({}, {})",
+                    x,
+                    y
+                );
+            }
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn regions_outside_texture_are_rejected() {
+        with_test_gpu(|gpu| {
+            let texture = copy_texture(
+                gpu,
+                TextureSize::D2(UVec2::new(4, 4)),
+                TextureFormat::Rgba8Unorm,
+            );
+            let region = UVec3::new(2, 2, 1);
+            let data = [0; 2 * 2 * 4];
+            assert!(texture
+                .write_region_pixels(gpu, &data, UVec3::new(3, 0, 0), region)
+                .is_err());
+            assert!(texture
+                .write_region_pixels(gpu, &data[..8], UVec3::ZERO, region)
+                .is_err());
+            assert!(texture
+                .read_region_pixels(gpu, UVec3::new(0, 0, 1), region)
+                .is_err());
+            assert!(texture
+                .read_region_pixels(gpu, UVec3::new(u32::MAX, 0, 0), region)
+                .is_err());
+            assert!(texture
+                .write_region_pixels(gpu, &data, UVec3::new(2, 2, 0), region)
+                .is_ok());
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn read_pixels_round_trip() {
+        with_test_gpu(|gpu| {
+            let size = UVec2::new(70, 3);
+            let texture = copy_texture(gpu, TextureSize::D2(size), TextureFormat::Rgba8Unorm);
+            let pixels: Vec<u8> = (0..size.x * size.y * 4).map(|i| (i % 251) as u8).collect();
+            texture.write_pixels(gpu, &pixels).unwrap();
+            assert_eq!(texture.read_pixels(gpu).unwrap(), pixels);
+            //A single pixel reads the same bytes as the full copy.
+            let pixel = texture
+                .read_region_pixels(gpu, UVec3::new(69, 2, 0), UVec3::ONE)
+                .unwrap();
+            let index = ((2 * size.x + 69) * 4) as usize;
+            assert_eq!(pixel, pixels[index..index + 4]);
+        });
+    }
+
+    #[test]
+    fn depth24_can_not_be_copied() {
+        let desc = TextureDesc {
+            size: TextureSize::D2(UVec2::ONE),
+            usage: TextureUsage::SOURCE,
+            format: TextureFormat::Depth24Plus,
+            mip_level_count: 1,
+        };
+        assert!(desc.bytes_per_pixel().is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn read_depth_near_and_far() {
+        with_test_gpu(|gpu| {
+            let size = UVec2::new(4, 2);
+            let texture = Texture::new(
+                gpu,
+                TextureDesc {
+                    size: TextureSize::D2(size),
+                    usage: TextureUsage::RENDER | TextureUsage::SOURCE,
+                    format: TextureFormat::Depth32Float,
+                    mip_level_count: 1,
+                },
+                None,
+            );
+            //Depth only pipeline drawing a fullscreen triangle at the near plane.
+            let shader = gpu.device.create_shader_module(ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(
+                    "@vertex
+                    fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                        let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+                        return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+                    }",
+                )),
+            });
+            let pipeline = gpu
+                .device
+                .create_render_pipeline(&RenderPipelineDescriptor {
+                    label: None,
+                    layout: None,
+                    vertex: VertexState {
+                        module: &shader,
+                        entry_point: "vs_main",
+                        buffers: &[],
+                    },
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: TextureFormat::Depth32Float,
+                        depth_write_enabled: true,
+                        depth_compare: wgpu::CompareFunction::Always,
+                        stencil: Default::default(),
+                        bias: Default::default(),
+                    }),
+                    multisample: MultisampleState::default(),
+                    fragment: None,
+                    multiview: None,
+                });
+            let view = texture
+                .texture
+                .create_view(&TextureViewDescriptor::default());
+            let mut encoder = gpu
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor { label: None });
+            {
+                //Cleared to the far plane, with the near triangle only on the left half.
+                let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &view,
+                        depth_ops: Some(Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: true,
+                        }),
+                        stencil_ops: None,
+                    }),
+                });
+                pass.set_pipeline(&pipeline);
+                pass.set_scissor_rect(0, 0, size.x / 2, size.y);
+                pass.draw(0..3, 0..1);
+            }
+            gpu.queue.submit(Some(encoder.finish()));
+
+            let data = texture.read_pixels(gpu).unwrap();
+            let depths = data
+                .chunks_exact(4)
+                .map(bytemuck::pod_read_unaligned::<f32>);
+            for (i, depth) in depths.enumerate() {
+                let expected = if (i as u32 % size.x) < size.x / 2 {
+                    0.0
+                } else {
+                    1.0
+                };
+                assert_eq!(depth, expected, "pixel {}", i);
+            }
+            //Depth32Float can be read back but not written.
+            assert!(texture.write_pixels(gpu, &data).is_err());
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a display and a GPU adapter"]
+    fn write_and_read_array_layers() {
+        with_test_gpu(|gpu| {
+            let size = UVec2::new(4, 2);
+            let texture = copy_texture(
+                gpu,
+                TextureSize::D2Array(size, 2),
+                TextureFormat::Rgba8Unorm,
+            );
+            let layer_len = (size.x * size.y * 4) as usize;
+            texture
+                .write_layer_pixels(gpu, 0, &vec![10; layer_len])
+                .unwrap();
+            texture
+                .write_layer_pixels(gpu, 1, &vec![200; layer_len])
+                .unwrap();
+
+            //Layers are read back one after the other.
+            let pixels = texture.read_pixels(gpu).unwrap();
+            assert_eq!(pixels.len(), layer_len * 2);
+            assert!(pixels[..layer_len].iter().all(|c| *c == 10));
+            assert!(pixels[layer_len..].iter().all(|c| *c == 200));
+            let second = texture
+                .read_region_pixels(gpu, UVec3::new(0, 0, 1), size.extend(1))
+                .unwrap();
+            assert_eq!(second, pixels[layer_len..]);
+        });
+    }
+}
@@ -0,0 +1,92 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use bytemuck::Pod;
+use wgpu::{util::DeviceExt, Buffer};
+
+use crate::{GpuCtx, ResourceId, ToBinder};
+
+/// Typed handle to a GPU uniform buffer, used to send values like matrices or time to shaders.
+///
+/// #Examples
+/// ``` no_run
+/// use tridify_rs::*;
+/// fn bind_mvp(gpu: &GpuCtx, brush: &mut Brush) {
+///     let identity = [
+///         [1.0, 0.0, 0.0, 0.0],
+///         [0.0, 1.0, 0.0, 0.0],
+///         [0.0, 0.0, 1.0, 0.0],
+///         [0.0, 0.0, 0.0, 1.0f32],
+///     ];
+///     let mvp = UniformBuffer::new(gpu, identity);
+///     //Matches `@group(0) @binding(0) var<uniform> mvp: mat4x4<f32>;` in the shader.
+///     brush.bind(0, 0, mvp.clone());
+///
+///     //Updating the buffer updates all brushes using it.
+///     mvp.update(gpu, identity);
+/// }
+/// ```
+pub struct UniformBuffer<T: Pod> {
+    buffer: Rc<Buffer>,
+    id: ResourceId,
+    _value: PhantomData<T>,
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    /// Creates a new uniform buffer initialized with the given value.
+    pub fn new(gpu: &GpuCtx, value: T) -> Self {
+        let buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(&value),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        Self {
+            buffer: Rc::new(buffer),
+            id: ResourceId::unique(),
+            _value: PhantomData,
+        }
+    }
+
+    /// Queues a write to update the buffer GPU data with the value provided.
+    pub fn update(&self, gpu: &GpuCtx, value: T) {
+        gpu.queue
+            .write_buffer(&self.buffer, 0, bytemuck::bytes_of(&value));
+    }
+}
+
+impl<T: Pod> ToBinder for UniformBuffer<T> {
+    fn get_layout(&self, index: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: index,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+
+    fn get_group(&self, index: u32) -> wgpu::BindGroupEntry {
+        wgpu::BindGroupEntry {
+            binding: index,
+            resource: self.buffer.as_entire_binding(),
+        }
+    }
+
+    fn resource_id(&self) -> ResourceId { self.id }
+
+    fn debug_name(&self) -> &'static str { "Uniform Buffer" }
+}
+
+impl<T: Pod> Clone for UniformBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: Rc::clone(&self.buffer),
+            id: self.id,
+            _value: PhantomData,
+        }
+    }
+}
@@ -1,17 +1,19 @@
 use std::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
-use glam::Vec3;
+use glam::{Mat4, Vec2, Vec3};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout};
 
 use crate::core::Color;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub pos: [f32; 3],
     pub color: Color,
     pub uv: [f32; 2],
+    pub normal: [f32; 3],
 }
 unsafe impl Pod for Vertex {
 }
@@ -36,13 +38,23 @@ impl Vertex {
                 shader_location: 2,
                 format: wgpu::VertexFormat::Float32x2,
             },
+            VertexAttribute {
+                offset: (size_of::<[f32; 3]>() + size_of::<Color>() + size_of::<[f32; 2]>())
+                    as BufferAddress,
+                shader_location: 3,
+                format: wgpu::VertexFormat::Float32x3,
+            },
         ],
     };
+    /// Normal used by vertices created without one, facing towards +Z.
+    pub const DEFAULT_NORMAL: [f32; 3] = [0.0, 0.0, 1.0];
+
     pub fn new(x: f32, y: f32, z: f32, c: Option<Color>, uv: Option<[f32; 2]>) -> Self {
         Self {
             pos: [x, y, z],
             color: c.unwrap_or(Color::WHITE),
             uv: uv.unwrap_or([0.0, 0.0]),
+            normal: Self::DEFAULT_NORMAL,
         }
     }
     pub fn from_vec(v: Vec3, c: Option<Color>, uv: Option<[f32; 2]>) -> Self {
@@ -50,8 +62,57 @@ impl Vertex {
             pos: [v.x, v.y, v.z],
             color: c.unwrap_or(Color::WHITE),
             uv: uv.unwrap_or([0.0, 0.0]),
+            normal: Self::DEFAULT_NORMAL,
         }
     }
+    /// Create a white vertex on the XY plane from a glam position.
+    pub fn from_vec2(pos: Vec2) -> Self { Self::from_vec(pos.extend(0.0), None, None) }
+    /// Create a white vertex from a glam position.
+    pub fn from_vec3(pos: Vec3) -> Self { Self::from_vec(pos, None, None) }
+    /// Create a vertex from glam position and texture coordinates.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use glam::{Vec2, Vec3};
+    /// use tridify_rs::*;
+    /// let v = Vertex::from_vec3_uv_color(Vec3::new(1.0, 2.0, 3.0), Vec2::new(0.5, 1.0), Color::RED);
+    /// assert_eq!(v.position(), Vec3::new(1.0, 2.0, 3.0));
+    /// assert_eq!(v.uv, [0.5, 1.0]);
+    /// assert_eq!(vertex!(Vec3::new(1.0, 2.0, 3.0), Color::RED).pos, v.pos);
+    /// ```
+    pub fn from_vec3_uv_color(pos: Vec3, uv: Vec2, color: Color) -> Self {
+        Self::from_vec(pos, Some(color), Some(uv.into()))
+    }
+    /// Same vertex using the given normal.
+    pub fn with_normal(mut self, normal: Vec3) -> Self {
+        self.normal = normal.into();
+        self
+    }
+    /// Create a vertex on the XY plane from pixel coordinates, with `(0, 0)` at the top left corner
+    /// and `size` at the bottom right one. Positions are converted into clip space, which goes from
+    /// -1 to 1 with Y pointing up, so they can be drawn without a camera.
+    ///
+    /// #Examples
+    /// ``` rust
+    /// use glam::Vec2;
+    /// use tridify_rs::*;
+    /// let size = Vec2::new(800.0, 600.0);
+    /// assert_eq!(Vertex::from_pixel(0.0, 0.0, size, None).pos, [-1.0, 1.0, 0.0]);
+    /// assert_eq!(Vertex::from_pixel(800.0, 600.0, size, None).pos, [1.0, -1.0, 0.0]);
+    /// assert_eq!(Vertex::from_pixel(800.0, 0.0, size, None).pos, [1.0, 1.0, 0.0]);
+    /// assert_eq!(Vertex::from_pixel(0.0, 600.0, size, None).pos, [-1.0, -1.0, 0.0]);
+    /// assert_eq!(Vertex::from_pixel(400.0, 300.0, size, None).pos, [0.0, 0.0, 0.0]);
+    /// ```
+    pub fn from_pixel(x: f32, y: f32, size: Vec2, c: Option<Color>) -> Self {
+        Self::new(2.0 * x / size.x - 1.0, 1.0 - 2.0 * y / size.y, 0.0, c, None)
+    }
+    /// Create a vertex on the XY plane with the given texture coordinates.
+    pub fn from_viewport_uv(x: f32, y: f32, u: f32, v: f32) -> Self {
+        Self::new(x, y, 0.0, None, Some([u, v]))
+    }
+    #[must_use]
+    #[inline]
+    pub fn position(&self) -> Vec3 { Vec3::from(self.pos) }
     #[must_use]
     #[inline]
     pub fn x(&self) -> f32 { self.pos[0] }
@@ -59,15 +120,83 @@ impl Vertex {
     #[inline]
     pub fn y(&self) -> f32 { self.pos[1] }
 }
+
+/// Per instance data used when drawing the same shapes many times in a single draw call.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Zeroable)]
+pub struct Instance {
+    pub transform: [[f32; 4]; 4],
+    pub color: Color,
+}
+unsafe impl Pod for Instance {
+}
+
+impl Instance {
+    /// Layout of the instance buffer. Transform columns use shader locations 4 to 7 and the color
+    /// uses location 8, after the ones used by `Vertex::DESC`.
+    pub const DESC: VertexBufferLayout<'static> = VertexBufferLayout {
+        array_stride: size_of::<Instance>() as BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &[
+            VertexAttribute {
+                offset: 0,
+                shader_location: 4,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[f32; 4]>() as BufferAddress,
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[[f32; 4]; 2]>() as BufferAddress,
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[[f32; 4]; 3]>() as BufferAddress,
+                shader_location: 7,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            VertexAttribute {
+                offset: size_of::<[[f32; 4]; 4]>() as BufferAddress,
+                shader_location: 8,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    };
+    pub fn new(transform: Mat4, color: Color) -> Self {
+        Self {
+            transform: transform.to_cols_array_2d(),
+            color,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! vertex {
+    ($pos:expr) => {
+        $crate::Vertex::from_vec3($pos)
+    };
+    ($pos:expr, $col:expr) => {
+        $crate::Vertex::from_vec($pos, Some($col), None)
+    };
     ($a:expr, $b:expr, $c:expr) => {
-        nucley::Vertex::new($a, $b, $c, None, None)
+        $crate::Vertex::new($a, $b, $c, None, None)
     };
     ($a:expr, $b:expr, $c:expr, $col:expr) => {
-        crate::Vertex::new($a, $b, $c, Some($col), None)
+        $crate::Vertex::new($a, $b, $c, Some($col), None)
     };
     ($a:expr, $b:expr, $c:expr, $col:expr, $uv:expr) => {
-        crate::Vertex::new($a, $b, $c, Some($col), Some($uv))
+        $crate::Vertex::new($a, $b, $c, Some($col), Some($uv))
+    };
+}
+#[macro_export]
+macro_rules! uv {
+    ($x:expr, $y:expr, $u:expr, $v:expr) => {
+        $crate::Vertex::from_viewport_uv($x, $y, $u, $v)
+    };
+    ($x:expr, $y:expr, $u:expr, $v:expr, $col:expr) => {
+        $crate::Vertex::new($x, $y, 0.0, Some($col), Some([$u, $v]))
     };
 }